@@ -1,5 +1,10 @@
-use aoc2024::solution_runners;
-use criterion::{criterion_group, criterion_main, Criterion};
+use aoc2024::{
+    day1, day11, day6,
+    day7::{self, Strategy},
+    day9, solution_runners,
+};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::{fs, fs::File, io::Write};
 
 fn criterion_benchmark(criterion: &mut Criterion) {
     let mut solution_runnners = solution_runners().into_iter().collect::<Vec<_>>();
@@ -12,7 +17,140 @@ fn criterion_benchmark(criterion: &mut Criterion) {
             });
         }
     }
+
+    for part in 1..=2 {
+        for strategy in [Strategy::Forward, Strategy::Backward] {
+            let stats = day7::search_stats(part, strategy).unwrap();
+            println!(
+                "day 7 part {part} {strategy:?}: explored {}, pruned {}",
+                stats.explored, stats.pruned
+            );
+            criterion.bench_function(
+                &format!("day 7 part {part} search {strategy:?}"),
+                |bencher| bencher.iter(|| day7::search_stats(part, strategy)),
+            );
+        }
+    }
+}
+
+// Deterministic disk map with file sizes 1-9 and gap sizes 0-9
+fn generate_disk_map(digits: usize) -> String {
+    let mut state: u64 = 0x2024_0009;
+    (0..digits)
+        .map(|index| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let random = state >> 33;
+            let digit = if index % 2 == 0 {
+                random % 9 + 1
+            } else {
+                random % 10
+            };
+            char::from_digit(digit as u32, 10).unwrap()
+        })
+        .collect()
+}
+
+fn disk_map_benchmark(criterion: &mut Criterion) {
+    for digits in [10_001, 100_001, 1_000_001] {
+        let disk_map = generate_disk_map(digits);
+        criterion.bench_function(&format!("day 9 whole files {digits} digits"), |bencher| {
+            bencher.iter(|| day9::whole_files_checksum(&disk_map))
+        });
+    }
+}
+
+// Deterministic location id lists in the puzzle's five digit format
+fn generate_id_lists(lines: usize) -> Vec<u8> {
+    let mut state: u64 = 0x2024_0001;
+    let mut next_id = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) % 90_000 + 10_000
+    };
+    let mut text = Vec::new();
+    for _ in 0..lines {
+        writeln!(text, "{}   {}", next_id(), next_id()).unwrap();
+    }
+    text
+}
+
+fn id_lists_benchmark(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("day 1 statistics");
+    group.sample_size(10);
+    for lines in [100_000, 1_000_000, 10_000_000] {
+        let text = generate_id_lists(lines);
+        let path = std::env::temp_dir().join(format!("aoc2024_day_1_{lines}_lines"));
+        fs::write(&path, &text).unwrap();
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_function(format!("{lines} lines"), |bencher| {
+            bencher.iter(|| day1::list_statistics(File::open(&path).unwrap()))
+        });
+        fs::remove_file(&path).unwrap();
+    }
+    group.finish();
+}
+
+// Deterministic square lab with about one obstacle in twenty cells, like the puzzle's,
+// and the guard facing up from the centre
+fn generate_lab(size: usize) -> String {
+    let mut state: u64 = 0x2024_002c;
+    let mut text = String::new();
+    for y in 0..size {
+        for x in 0..size {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            text.push(if (x, y) == (size / 2, size / 2) {
+                '^'
+            } else if (state >> 33).is_multiple_of(20) {
+                '#'
+            } else {
+                '.'
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn lab_benchmark(criterion: &mut Criterion) {
+    let lab = generate_lab(130);
+    criterion.bench_function("day 6 loop obstacles 130x130", |bencher| {
+        bencher.iter(|| day6::loop_obstacle_count(&lab).unwrap())
+    });
+}
+
+// Eight stones with the digit counts of a puzzle input
+const STONES: &str = "4 4841539 66 5279 49207 134 609568 0";
+
+fn stone_count_benchmark(criterion: &mut Criterion) {
+    let mut blink_counts = vec![25, 75];
+    // Counts past 75 blinks overflow u64, and past 200 blinks u128
+    if cfg!(any(feature = "u128-answers", feature = "bigint-answers")) {
+        blink_counts.push(200);
+    }
+    if cfg!(feature = "bigint-answers") {
+        blink_counts.push(1000);
+    }
+    let mut group = criterion.benchmark_group("day 11 stone count");
+    group.sample_size(10);
+    for blinks in blink_counts {
+        group.bench_function(format!("{blinks} blinks"), |bencher| {
+            bencher.iter(|| day11::stone_count(STONES, blinks).unwrap())
+        });
+    }
+    group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    disk_map_benchmark,
+    id_lists_benchmark,
+    stone_count_benchmark,
+    lab_benchmark
+);
 criterion_main!(benches);
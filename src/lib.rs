@@ -1,5 +1,8 @@
 mod math;
+mod parser;
+mod region;
 mod utils;
+pub mod visualize;
 mod y2024;
 
 use reqwest::{blocking, header::COOKIE, Url};
@@ -11,10 +14,16 @@ use std::{
     path::PathBuf,
     vec,
 };
+use visualize::Recorder;
+
+pub use y2024::{day1, day11, day6, day7, day9};
 
 pub type AnyError = Box<dyn Error>;
 pub type SolutionResult = Result<String, AnyError>;
 pub type Runner = Box<dyn Fn() -> SolutionResult>;
+pub type Visualizer = fn(&mut Recorder) -> Result<(), AnyError>;
+pub type Explainer = fn(u32) -> SolutionResult;
+pub type Scaler = fn(usize, usize) -> SolutionResult;
 
 const INPUT_DIR: &str = "input";
 const AOC_SESSION_COOKIE_FILE: &str = "aoc_session_cookie.txt";
@@ -57,6 +66,37 @@ pub fn solution_runners() -> HashMap<u32, Vec<Runner>> {
     solution_runners
 }
 
+pub fn visualizers() -> HashMap<u32, Visualizer> {
+    use y2024::*;
+    HashMap::from([
+        (6, day6::visualize as Visualizer),
+        (14, day14::visualize),
+        (15, day15::visualize),
+        (16, day16::visualize),
+    ])
+}
+
+pub fn explainers() -> HashMap<u32, Explainer> {
+    use y2024::*;
+    HashMap::from([
+        (1, day1::explain as Explainer),
+        (3, day3::explain),
+        (4, day4::explain),
+        (5, day5::explain),
+        (7, day7::explain),
+        (8, day8::explain),
+        (9, day9::explain),
+        (10, day10::explain),
+        (15, day15::explain),
+        (16, day16::explain),
+    ])
+}
+
+pub fn scalers() -> HashMap<u32, Scaler> {
+    use y2024::*;
+    HashMap::from([(15, day15::scaled_gps as Scaler)])
+}
+
 pub fn get_text_file(url: &str) -> Result<File, Box<dyn Error>> {
     let directory = INPUT_DIR;
     let parsed_url = Url::parse(url)?;
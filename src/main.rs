@@ -1,11 +1,12 @@
-use aoc2024::{solution_runners, Runner};
+use aoc2024::{explainers, scalers, solution_runners, visualize::Recorder, visualizers, Runner};
 use clap::{builder::RangedI64ValueParser, value_parser, Parser, Subcommand};
-use std::error::Error;
+use std::{error::Error, fs, num::ParseIntError, path::PathBuf};
 
 pub const FIRST_DAY: i64 = 1;
 pub const LAST_DAY: i64 = 25;
 pub const FIRST_PART: i64 = 1;
 pub const LAST_PART: i64 = 2;
+pub const DUMP_FRAME_SCALE: usize = 4;
 
 pub type AnyError = Box<dyn Error>;
 
@@ -30,8 +31,33 @@ enum Command {
             value_parser = part_parser(),
         )]
         part: Option<u32>,
+        #[arg(long)]
+        dump_frame: Option<PathBuf>,
+        #[arg(long)]
+        explain: bool,
+        // Box size as WIDTH or WIDTHxHEIGHT
+        #[arg(long, value_parser = parse_scale)]
+        scale: Option<(usize, usize)>,
     },
     All,
+    Viz {
+        #[arg(
+            short,
+            long,
+            value_parser = day_parser(),
+        )]
+        day: u32,
+        #[arg(short, long, default_value = "viz")]
+        output: PathBuf,
+        #[arg(short, long, default_value_t = 1)]
+        every: usize,
+        #[arg(short, long, default_value_t = 4)]
+        scale: usize,
+        #[arg(long, default_value_t = 5)]
+        delay: u16,
+        #[arg(short, long)]
+        terminal: bool,
+    },
 }
 
 fn main() -> Result<(), AnyError> {
@@ -39,8 +65,16 @@ fn main() -> Result<(), AnyError> {
 
     let solution_runners = solution_runners();
 
+    let mut frame_dump = None;
+    let mut explainer = None;
     let selected_runners = match cli.command {
-        Command::Run { day, mut part } => {
+        Command::Run {
+            day,
+            mut part,
+            dump_frame,
+            explain,
+            scale,
+        } => {
             let day_or_max = day.unwrap_or(
                 *solution_runners
                     .keys()
@@ -51,9 +85,30 @@ fn main() -> Result<(), AnyError> {
                 .get(&day_or_max)
                 .ok_or(format!("Day {day_or_max:?} not implemented"))?;
 
+            if let Some((width, height)) = scale {
+                let scaler = *scalers()
+                    .get(&day_or_max)
+                    .ok_or(format!("Day {day_or_max:?} cannot be scaled"))?;
+                println!("Day {} scaled to {}x{}", day_or_max, width, height);
+                println!("Solution: {}\n", scaler(width, height)?);
+                return Ok(());
+            }
             if day == None && part == None {
                 part = Some(runners.len() as u32);
             }
+            if let Some(path) = dump_frame {
+                let visualizer = visualizers()
+                    .remove(&day_or_max)
+                    .ok_or(format!("Day {day_or_max:?} has no visualization"))?;
+                frame_dump = Some((visualizer, path));
+            }
+            if explain {
+                explainer = Some(
+                    *explainers()
+                        .get(&day_or_max)
+                        .ok_or(format!("Day {day_or_max:?} has no explanation"))?,
+                );
+            }
 
             if let Some(part) = part {
                 let runner = runners
@@ -78,11 +133,54 @@ fn main() -> Result<(), AnyError> {
             selected.sort_by_key(|(day, part, _)| (*day, *part));
             selected
         }
+        Command::Viz {
+            day,
+            output,
+            every,
+            scale,
+            delay,
+            terminal,
+        } => {
+            let visualizer = visualizers()
+                .remove(&day)
+                .ok_or(format!("Day {day:?} has no visualization"))?;
+            fs::create_dir_all(&output)?;
+            let png_path = output.join(format!("day_{day}.png"));
+            let gif_path = output.join(format!("day_{day}.gif"));
+            let mut recorder = Recorder::new(every).with_gif(&gif_path, scale, delay);
+            visualizer(&mut recorder)?;
+            recorder.finish()?;
+
+            let last_frame = recorder.last_frame().ok_or("No frames recorded")?;
+            if terminal {
+                print!("{}", last_frame.to_ansi_string());
+            }
+            last_frame.save_png(&png_path, scale)?;
+            println!(
+                "Recorded {} frames to {} and {}",
+                recorder.frame_count(),
+                gif_path.display(),
+                png_path.display()
+            );
+            return Ok(());
+        }
     };
     for (day, part, runner) in selected_runners {
         println!("Day {} part {}", day, part);
         let solution = runner()?;
         println!("Solution: {}\n", solution);
+        if let Some(explainer) = explainer {
+            println!("Explanation:\n{}", explainer(part)?);
+        }
+    }
+
+    if let Some((visualizer, path)) = frame_dump {
+        let mut recorder = Recorder::new(usize::MAX);
+        visualizer(&mut recorder)?;
+        let last_frame = recorder.last_frame().ok_or("No frames recorded")?;
+        print!("{}", last_frame.to_ansi_string());
+        last_frame.save_png(&path, DUMP_FRAME_SCALE)?;
+        println!("Frame saved to {}", path.display());
     }
 
     Ok(())
@@ -95,3 +193,10 @@ fn day_parser() -> RangedI64ValueParser<u32> {
 fn part_parser() -> RangedI64ValueParser<u32> {
     value_parser!(u32).range(1..=10)
 }
+
+fn parse_scale(text: &str) -> Result<(usize, usize), ParseIntError> {
+    Ok(match text.split_once('x') {
+        Some((width, height)) => (width.parse()?, height.parse()?),
+        None => (text.parse()?, 1),
+    })
+}
@@ -1,12 +1,89 @@
 #![allow(dead_code)]
 
+use crate::AnyError;
 use ndarray::{Ix2, NdIndex};
-use num::{traits::{SaturatingAdd, SaturatingSub}, CheckedAdd, CheckedSub, NumCast, Signed, ToPrimitive};
+use num::{
+    integer::ExtendedGcd,
+    rational::Ratio,
+    traits::{SaturatingAdd, SaturatingSub},
+    CheckedAdd, CheckedMul, CheckedSub, FromPrimitive, Integer, NumCast, One, Signed, ToPrimitive,
+    Zero,
+};
 use std::{
     fmt::Debug,
     ops::{Add, Mul, Neg, Sub},
 };
 
+#[cfg(not(any(feature = "u128-answers", feature = "bigint-answers")))]
+pub type Answer = u64;
+#[cfg(all(feature = "u128-answers", not(feature = "bigint-answers")))]
+pub type Answer = u128;
+#[cfg(feature = "bigint-answers")]
+pub type Answer = num::BigInt;
+
+pub trait OrOverflow<T> {
+    fn or_overflow(self) -> Result<T, AnyError>;
+}
+
+impl<T> OrOverflow<T> for Option<T> {
+    fn or_overflow(self) -> Result<T, AnyError> {
+        self.ok_or_else(|| {
+            "Arithmetic overflow, try the u128-answers or bigint-answers feature".into()
+        })
+    }
+}
+
+pub fn to_answer<T>(value: T) -> Result<Answer, AnyError>
+where
+    T: ToPrimitive,
+{
+    value.to_i128().and_then(Answer::from_i128).or_overflow()
+}
+
+pub fn checked_sum<I>(values: I) -> Result<Answer, AnyError>
+where
+    I: IntoIterator<Item = Answer>,
+{
+    values
+        .into_iter()
+        .try_fold(Answer::zero(), |sum, value| {
+            CheckedAdd::checked_add(&sum, &value)
+        })
+        .or_overflow()
+}
+
+pub fn try_checked_sum<I>(values: I) -> Result<Answer, AnyError>
+where
+    I: IntoIterator<Item = Result<Answer, AnyError>>,
+{
+    values.into_iter().try_fold(Answer::zero(), |sum, value| {
+        CheckedAdd::checked_add(&sum, &value?).or_overflow()
+    })
+}
+
+pub fn checked_product<I>(values: I) -> Result<Answer, AnyError>
+where
+    I: IntoIterator<Item = Answer>,
+{
+    values
+        .into_iter()
+        .try_fold(Answer::one(), |product, value| {
+            CheckedMul::checked_mul(&product, &value)
+        })
+        .or_overflow()
+}
+
+pub fn try_checked_product<I>(values: I) -> Result<Answer, AnyError>
+where
+    I: IntoIterator<Item = Result<Answer, AnyError>>,
+{
+    values
+        .into_iter()
+        .try_fold(Answer::one(), |product, value| {
+            CheckedMul::checked_mul(&product, &value?).or_overflow()
+        })
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct Vec2<T> {
     pub x: T,
@@ -37,7 +114,7 @@ where
         self.x >= lower_bound.x
             && self.y >= lower_bound.y
             && self.x < upper_bound.x
-            && self.y < upper_bound.x
+            && self.y < upper_bound.y
     }
 }
 
@@ -143,7 +220,6 @@ where
     }
 }
 
-
 impl<T> SaturatingAdd for Vec2<T>
 where
     T: SaturatingAdd,
@@ -209,3 +285,236 @@ unsafe impl NdIndex<Ix2> for Vec2<usize> {
         (self.y * strides[0] + self.x * strides[1]) as isize
     }
 }
+
+pub fn chinese_remainder<T>(congruences: &[(T, T)]) -> Option<(T, T)>
+where
+    T: Integer + Clone,
+{
+    congruences.iter().cloned().try_fold(
+        (T::zero(), T::one()),
+        |(residue, modulus), (other_residue, other_modulus)| {
+            let ExtendedGcd { gcd, x, .. } = modulus.extended_gcd(&other_modulus);
+            let diff = other_residue - residue.clone();
+            if !(diff.clone() % gcd.clone()).is_zero() {
+                return None;
+            }
+            let other_modulus_reduced = other_modulus / gcd.clone();
+            let combined_modulus = modulus.clone() * other_modulus_reduced.clone();
+            let shift = (diff / gcd * x).mod_floor(&other_modulus_reduced);
+            let combined_residue = (residue + modulus * shift).mod_floor(&combined_modulus);
+            Some((combined_residue, combined_modulus))
+        },
+    )
+}
+
+// Non-negative integer solution (u, v) of u * a + v * b = target minimizing
+// u * cost.x + v * cost.y, with u <= max.x and v <= max.y when bounded
+pub fn solve_integer_2x2<T>(
+    a: Vec2<T>,
+    b: Vec2<T>,
+    target: Vec2<T>,
+    cost: Vec2<T>,
+    max: Option<Vec2<T>>,
+) -> Option<Vec2<T>>
+where
+    T: Integer + Signed + Copy,
+{
+    let determinant = a.x * b.y - a.y * b.x;
+    if !determinant.is_zero() {
+        // Cramer's rule
+        let u = Ratio::new(target.x * b.y - target.y * b.x, determinant);
+        let v = Ratio::new(a.x * target.y - a.y * target.x, determinant);
+        if !u.is_integer() || !v.is_integer() {
+            return None;
+        }
+        return Some(Vec2 {
+            x: u.to_integer(),
+            y: v.to_integer(),
+        })
+        .filter(|s| !s.x.is_negative() && !s.y.is_negative())
+        .filter(|s| max.is_none_or(|max| s.x <= max.x && s.y <= max.y));
+    }
+
+    // Collinear buttons, the target must lie on the same line
+    let cross = |p: Vec2<T>, q: Vec2<T>| p.x * q.y - p.y * q.x;
+    if !cross(a, target).is_zero() || !cross(b, target).is_zero() {
+        return None;
+    }
+    let (a_1d, b_1d, target_1d) = if !a.x.is_zero() || !b.x.is_zero() {
+        (a.x, b.x, target.x)
+    } else if !a.y.is_zero() || !b.y.is_zero() {
+        (a.y, b.y, target.y)
+    } else {
+        return Some(Vec2 {
+            x: T::zero(),
+            y: T::zero(),
+        })
+        .filter(|_| target.x.is_zero() && target.y.is_zero());
+    };
+    solve_integer_1d(a_1d, b_1d, target_1d, cost, max)
+}
+
+// Non-negative integer solution (u, v) of u * a + v * b = target minimizing
+// u * cost.x + v * cost.y, searched over the lattice of all integer solutions within
+// the bounds
+fn solve_integer_1d<T>(
+    a: T,
+    b: T,
+    target: T,
+    cost: Vec2<T>,
+    max: Option<Vec2<T>>,
+) -> Option<Vec2<T>>
+where
+    T: Integer + Signed + Copy,
+{
+    let ExtendedGcd { gcd, x, y } = a.extended_gcd(&b);
+    if !(target % gcd).is_zero() {
+        return None;
+    }
+    // u = u_0 + k * b_step, v = v_0 - k * a_step
+    let (u_0, v_0) = (x * (target / gcd), y * (target / gcd));
+    let (a_step, b_step) = (a / gcd, b / gcd);
+
+    let mut k_min: Option<T> = None;
+    let mut k_max: Option<T> = None;
+    // p + k * q >= 0
+    let mut constraints = vec![(u_0, b_step), (v_0, -a_step)];
+    if let Some(max) = max {
+        constraints.extend([(max.x - u_0, -b_step), (max.y - v_0, a_step)]);
+    }
+    for (p, q) in constraints {
+        if q.is_positive() {
+            let bound = -(p.div_floor(&q));
+            k_min = Some(k_min.map_or(bound, |k| k.max(bound)));
+        } else if q.is_negative() {
+            let bound = p.div_floor(&-q);
+            k_max = Some(k_max.map_or(bound, |k| k.min(bound)));
+        } else if p.is_negative() {
+            return None;
+        }
+    }
+    let slope = cost.x * b_step - cost.y * a_step;
+    let k = match (k_min, k_max) {
+        (Some(k_min), Some(k_max)) if k_min > k_max => return None,
+        (Some(k_min), _) if !slope.is_negative() => k_min,
+        (_, Some(k_max)) if !slope.is_positive() => k_max,
+        (None, None) if slope.is_zero() => T::zero(),
+        _ => return None,
+    };
+    Some(Vec2 {
+        x: u_0 + k * b_step,
+        y: v_0 - k * a_step,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COST: Vec2<i64> = Vec2 { x: 3, y: 1 };
+
+    fn v(x: i64, y: i64) -> Vec2<i64> {
+        Vec2 { x, y }
+    }
+
+    #[test]
+    fn solve_integer_2x2_unique() {
+        assert_eq!(
+            solve_integer_2x2(v(94, 34), v(22, 67), v(8400, 5400), COST, None),
+            Some(v(80, 40))
+        );
+        assert_eq!(
+            solve_integer_2x2(v(17, 86), v(84, 37), v(7870, 6450), COST, None),
+            Some(v(38, 86))
+        );
+    }
+
+    #[test]
+    fn solve_integer_2x2_no_solution() {
+        // Fractional presses
+        assert_eq!(
+            solve_integer_2x2(v(26, 66), v(67, 21), v(12748, 12176), COST, None),
+            None
+        );
+        // Negative presses
+        assert_eq!(
+            solve_integer_2x2(v(1, 0), v(0, 1), v(-1, 5), COST, None),
+            None
+        );
+        // Collinear buttons, prize off the line
+        assert_eq!(
+            solve_integer_2x2(v(1, 1), v(2, 2), v(3, 4), COST, None),
+            None
+        );
+        // Collinear buttons, prize on the line but not on the lattice
+        assert_eq!(
+            solve_integer_2x2(v(2, 2), v(4, 4), v(5, 5), COST, None),
+            None
+        );
+    }
+
+    #[test]
+    fn solve_integer_2x2_collinear() {
+        // B is cheaper per unit of distance
+        assert_eq!(
+            solve_integer_2x2(v(3, 3), v(1, 1), v(10, 10), COST, None),
+            Some(v(0, 10))
+        );
+        // A is cheaper per unit of distance, but B is needed to reach the prize
+        assert_eq!(
+            solve_integer_2x2(v(4, 8), v(1, 2), v(10, 20), COST, None),
+            Some(v(2, 2))
+        );
+        // Same button twice
+        assert_eq!(
+            solve_integer_2x2(v(2, 3), v(2, 3), v(10, 15), COST, None),
+            Some(v(0, 5))
+        );
+        // Only B moves the claw
+        assert_eq!(
+            solve_integer_2x2(v(0, 0), v(0, 5), v(0, 15), COST, None),
+            Some(v(0, 3))
+        );
+        assert_eq!(
+            solve_integer_2x2(v(0, 0), v(0, 0), v(0, 0), COST, None),
+            Some(v(0, 0))
+        );
+        // The cheapest unbounded solution needs more presses than allowed
+        assert_eq!(
+            solve_integer_2x2(v(55, 93), v(110, 186), v(11165, 18879), COST, None),
+            Some(v(1, 101))
+        );
+        assert_eq!(
+            solve_integer_2x2(
+                v(55, 93),
+                v(110, 186),
+                v(11165, 18879),
+                COST,
+                Some(v(100, 100))
+            ),
+            Some(v(3, 100))
+        );
+        assert_eq!(
+            solve_integer_2x2(
+                v(55, 93),
+                v(110, 186),
+                v(11165, 18879),
+                COST,
+                Some(v(2, 100))
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn solve_integer_2x2_bounded() {
+        assert_eq!(
+            solve_integer_2x2(v(94, 34), v(22, 67), v(8400, 5400), COST, Some(v(100, 100))),
+            Some(v(80, 40))
+        );
+        assert_eq!(
+            solve_integer_2x2(v(94, 34), v(22, 67), v(8400, 5400), COST, Some(v(79, 100))),
+            None
+        );
+    }
+}
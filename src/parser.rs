@@ -0,0 +1,94 @@
+use std::ops::Range;
+
+// Arguments longer than this are not part of a valid instruction
+const MAX_DIGITS: usize = 3;
+
+pub struct Rule<I> {
+    pub name: &'static str,
+    pub arity: usize,
+    pub build: fn(&[i64]) -> I,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Spanned<T> {
+    pub value: T,
+    // Byte offsets into the parsed text
+    pub span: Range<usize>,
+}
+
+// Yields every well-formed `name(arg,...)` call of the grammar, skipping the corrupted
+// bytes in between
+pub struct Parser<'a, I> {
+    bytes: &'a [u8],
+    grammar: &'a [Rule<I>],
+    position: usize,
+}
+
+impl<'a, I> Parser<'a, I> {
+    pub fn new(text: &'a str, grammar: &'a [Rule<I>]) -> Self {
+        Self {
+            bytes: text.as_bytes(),
+            grammar,
+            position: 0,
+        }
+    }
+
+    fn parse_call(&self, rule: &Rule<I>, start: usize) -> Option<Spanned<I>> {
+        let mut position = start;
+        self.expect(&mut position, rule.name.as_bytes())?;
+        self.expect(&mut position, b"(")?;
+        let mut args = Vec::with_capacity(rule.arity);
+        for index in 0..rule.arity {
+            if index > 0 {
+                self.expect(&mut position, b",")?;
+            }
+            args.push(self.parse_number(&mut position)?);
+        }
+        self.expect(&mut position, b")")?;
+        Some(Spanned {
+            value: (rule.build)(&args),
+            span: start..position,
+        })
+    }
+
+    fn parse_number(&self, position: &mut usize) -> Option<i64> {
+        let digits = self.bytes[*position..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if !(1..=MAX_DIGITS).contains(&digits) {
+            return None;
+        }
+        let number = self.bytes[*position..*position + digits]
+            .iter()
+            .fold(0, |number, digit| number * 10 + i64::from(digit - b'0'));
+        *position += digits;
+        Some(number)
+    }
+
+    fn expect(&self, position: &mut usize, expected: &[u8]) -> Option<()> {
+        self.bytes[*position..].starts_with(expected).then(|| {
+            *position += expected.len();
+        })
+    }
+}
+
+impl<I> Iterator for Parser<'_, I> {
+    type Item = Spanned<I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position < self.bytes.len() {
+            let start = self.position;
+            if let Some(instruction) = self
+                .grammar
+                .iter()
+                .find_map(|rule| self.parse_call(rule, start))
+            {
+                self.position = instruction.span.end;
+                return Some(instruction);
+            }
+            self.position += 1;
+        }
+        None
+    }
+}
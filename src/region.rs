@@ -0,0 +1,316 @@
+#![allow(dead_code)]
+
+use crate::{
+    math::Vec2,
+    utils::{label_components, Connectivity},
+};
+use ndarray::Array2;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+const NEIGHBORS: [Vec2<isize>; 4] = [
+    Vec2 { x: 0, y: -1 },
+    Vec2 { x: 1, y: 0 },
+    Vec2 { x: 0, y: 1 },
+    Vec2 { x: -1, y: 0 },
+];
+
+// 4-connected components of equal grid cells
+#[derive(Clone, Debug)]
+pub struct Regions {
+    labels: Array2<usize>,
+    // Row-major sorted cells per label
+    cells: Vec<Vec<Vec2<usize>>>,
+}
+
+impl Regions {
+    pub fn new<T: PartialEq>(grid: &Array2<T>) -> Self {
+        let (labels, len) = label_components(grid, Connectivity::Four, |a, b| a == b);
+        let mut cells = vec![Vec::new(); len];
+        for (index, label) in labels.indexed_iter() {
+            cells[*label].push(Vec2::from_index_tuple(index));
+        }
+        Self { labels, cells }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn label(&self, index: Vec2<usize>) -> Option<usize> {
+        self.labels.get(index).copied()
+    }
+
+    pub fn cells(&self, region: usize) -> &[Vec2<usize>] {
+        &self.cells[region]
+    }
+
+    pub fn area(&self, region: usize) -> usize {
+        self.cells[region].len()
+    }
+
+    pub fn perimeter(&self, region: usize) -> usize {
+        self.cells[region]
+            .iter()
+            .map(|cell| {
+                NEIGHBORS
+                    .iter()
+                    .filter(|offset| !self.contains(region, *cell, **offset))
+                    .count()
+            })
+            .sum()
+    }
+
+    // Every corner of the boundary starts a new side
+    pub fn sides(&self, region: usize) -> usize {
+        self.boundary(region).iter().map(Vec::len).sum()
+    }
+
+    // Inclusive (min, max) cells
+    pub fn bounding_box(&self, region: usize) -> (Vec2<usize>, Vec2<usize>) {
+        let cells = &self.cells[region];
+        let min_x = cells.iter().map(|cell| cell.x).min().unwrap();
+        let max_x = cells.iter().map(|cell| cell.x).max().unwrap();
+        (
+            Vec2 {
+                x: min_x,
+                y: cells[0].y,
+            },
+            Vec2 {
+                x: max_x,
+                y: cells[cells.len() - 1].y,
+            },
+        )
+    }
+
+    // Labels of the regions which cannot reach the grid border without crossing this
+    // region, including regions nested further inside
+    pub fn holes(&self, region: usize) -> Vec<usize> {
+        let (rows, cols) = self.labels.dim();
+        let mut outside = Array2::from_elem((rows, cols), false);
+        let mut stack = self
+            .labels
+            .indexed_iter()
+            .filter(|((row, col), label)| {
+                **label != region
+                    && (*row == 0 || *col == 0 || *row == rows - 1 || *col == cols - 1)
+            })
+            .map(|(index, _)| Vec2::from_index_tuple(index))
+            .collect::<Vec<_>>();
+        for index in &stack {
+            outside[*index] = true;
+        }
+        while let Some(index) = stack.pop() {
+            for neighbor in iter_neighbors(index) {
+                if self
+                    .labels
+                    .get(neighbor)
+                    .is_some_and(|label| *label != region)
+                    && !outside[neighbor]
+                {
+                    outside[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        let holes: FxHashSet<usize> = self
+            .labels
+            .indexed_iter()
+            .filter(|(index, label)| **label != region && !outside[*index])
+            .map(|(_, label)| *label)
+            .collect();
+        let mut holes = holes.into_iter().collect::<Vec<_>>();
+        holes.sort_unstable();
+        holes
+    }
+
+    // Closed loops of corner coordinates on the cell lattice, where cell (x, y) spans
+    // (x, y) to (x + 1, y + 1). The outer loop comes first and runs clockwise, the
+    // loops around holes run counterclockwise.
+    pub fn boundary(&self, region: usize) -> Vec<Vec<Vec2<usize>>> {
+        // Directed edges with the region on their right hand side
+        let mut edges = Vec::new();
+        for cell in &self.cells[region] {
+            let Vec2 { x, y } = *cell;
+            let corners = [
+                Vec2 { x, y },
+                Vec2 { x: x + 1, y },
+                Vec2 { x: x + 1, y: y + 1 },
+                Vec2 { x, y: y + 1 },
+            ];
+            for (side, offset) in NEIGHBORS.iter().enumerate() {
+                if !self.contains(region, *cell, *offset) {
+                    edges.push((corners[side], corners[(side + 1) % 4]));
+                }
+            }
+        }
+        let mut outgoing: FxHashMap<Vec2<usize>, Vec<Vec2<usize>>> = FxHashMap::default();
+        for (start, end) in &edges {
+            outgoing.entry(*start).or_default().push(*end);
+        }
+
+        let mut used = FxHashSet::default();
+        let mut loops = Vec::new();
+        for edge in edges {
+            if used.contains(&edge) {
+                continue;
+            }
+            let mut vertices = Vec::new();
+            let (mut start, mut end) = edge;
+            while used.insert((start, end)) {
+                vertices.push(start);
+                let heading = direction(start, end);
+                // Where two loops touch at a point, keep hugging the region
+                let next = outgoing[&end]
+                    .iter()
+                    .filter(|next| !used.contains(&(end, **next)))
+                    .max_by_key(|next| direction(end, **next) == right_turn(heading))
+                    .copied();
+                let Some(next) = next else {
+                    break;
+                };
+                (start, end) = (end, next);
+            }
+            let corners = (0..vertices.len())
+                .filter(|i| {
+                    let previous = vertices[(i + vertices.len() - 1) % vertices.len()];
+                    let next = vertices[(i + 1) % vertices.len()];
+                    direction(previous, vertices[*i]) != direction(vertices[*i], next)
+                })
+                .map(|i| vertices[i])
+                .collect();
+            loops.push(corners);
+        }
+        loops
+    }
+
+    fn contains(&self, region: usize, cell: Vec2<usize>, offset: Vec2<isize>) -> bool {
+        cell.signed_add(offset)
+            .and_then(|neighbor| self.labels.get(neighbor))
+            .is_some_and(|label| *label == region)
+    }
+}
+
+fn iter_neighbors(index: Vec2<usize>) -> impl Iterator<Item = Vec2<usize>> {
+    NEIGHBORS
+        .into_iter()
+        .filter_map(move |offset| index.signed_add(offset))
+}
+
+fn direction(start: Vec2<usize>, end: Vec2<usize>) -> Vec2<isize> {
+    end.convert::<isize>().unwrap() - start.convert::<isize>().unwrap()
+}
+
+// Clockwise on screen, where y grows downwards
+fn right_turn(direction: Vec2<isize>) -> Vec2<isize> {
+    Vec2 {
+        x: -direction.y,
+        y: direction.x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Array2<char> {
+        let cols = rows[0].len();
+        Array2::from_shape_vec(
+            (rows.len(), cols),
+            rows.iter().flat_map(|row| row.chars()).collect(),
+        )
+        .unwrap()
+    }
+
+    fn costs(rows: &[&str]) -> (usize, usize) {
+        let regions = Regions::new(&grid(rows));
+        (0..regions.len())
+            .map(|region| {
+                let area = regions.area(region);
+                (
+                    area * regions.perimeter(region),
+                    area * regions.sides(region),
+                )
+            })
+            .fold((0, 0), |(perimeter, sides), cost| {
+                (perimeter + cost.0, sides + cost.1)
+            })
+    }
+
+    #[test]
+    fn small_example() {
+        assert_eq!(costs(&["AAAA", "BBCD", "BBCC", "EEEC"]), (140, 80));
+    }
+
+    #[test]
+    fn nested_example() {
+        let rows = ["OOOOO", "OXOXO", "OOOOO", "OXOXO", "OOOOO"];
+        assert_eq!(costs(&rows), (772, 436));
+
+        let regions = Regions::new(&grid(&rows));
+        let outer = regions.label(Vec2 { x: 0, y: 0 }).unwrap();
+        assert_eq!(regions.holes(outer).len(), 4);
+        assert_eq!(regions.boundary(outer).len(), 5);
+        assert!(regions
+            .holes(regions.label(Vec2 { x: 1, y: 1 }).unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn e_example() {
+        let rows = ["EEEEE", "EXXXX", "EEEEE", "EXXXX", "EEEEE"];
+        assert_eq!(costs(&rows).1, 236);
+
+        let regions = Regions::new(&grid(&rows));
+        let e = regions.label(Vec2 { x: 0, y: 0 }).unwrap();
+        assert_eq!(regions.sides(e), 12);
+        assert_eq!(
+            regions.bounding_box(e),
+            (Vec2 { x: 0, y: 0 }, Vec2 { x: 4, y: 4 })
+        );
+    }
+
+    #[test]
+    fn touching_corners_example() {
+        let rows = ["AAAAAA", "AAABBA", "AAABBA", "ABBAAA", "ABBAAA", "AAAAAA"];
+        assert_eq!(costs(&rows).1, 368);
+
+        let regions = Regions::new(&grid(&rows));
+        let a = regions.label(Vec2 { x: 0, y: 0 }).unwrap();
+        assert_eq!(regions.sides(a), 12);
+        assert_eq!(regions.holes(a).len(), 2);
+    }
+
+    #[test]
+    fn larger_example() {
+        let rows = [
+            "RRRRIICCFF",
+            "RRRRIICCCF",
+            "VVRRRCCFFF",
+            "VVRCCCJFFF",
+            "VVVVCJJCFE",
+            "VVIVCCJJEE",
+            "VVIIICJJEE",
+            "MIIIIIJJEE",
+            "MIIISIJEEE",
+            "MMMISSJEEE",
+        ];
+        assert_eq!(costs(&rows), (1930, 1206));
+    }
+
+    #[test]
+    fn boundary_corners() {
+        let regions = Regions::new(&grid(&["AB", "AA"]));
+        let a = regions.label(Vec2 { x: 0, y: 0 }).unwrap();
+        assert_eq!(
+            regions.boundary(a),
+            vec![vec![
+                Vec2 { x: 0, y: 0 },
+                Vec2 { x: 1, y: 0 },
+                Vec2 { x: 1, y: 1 },
+                Vec2 { x: 2, y: 1 },
+                Vec2 { x: 2, y: 2 },
+                Vec2 { x: 0, y: 2 },
+            ]]
+        );
+    }
+}
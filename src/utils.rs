@@ -1,7 +1,314 @@
 #![allow(dead_code)]
 
+use crate::math::Vec2;
+use ndarray::Array2;
 use petgraph::prelude::*;
-use rustc_hash::FxBuildHasher;
+use rustc_hash::{FxBuildHasher, FxHashMap};
 
 pub type FxDiGraphMap<N, E> = GraphMap<N, E, Directed, FxBuildHasher>;
-pub type FxUnGraphMap<N, E> = GraphMap<N, E, Undirected, FxBuildHasher>;
\ No newline at end of file
+pub type FxUnGraphMap<N, E> = GraphMap<N, E, Undirected, FxBuildHasher>;
+
+#[derive(Clone, Debug)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    // Returns whether the bit was newly set
+    pub fn insert(&mut self, index: usize) -> bool {
+        let (word, mask) = (index / 64, 1 << (index % 64));
+        let inserted = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        inserted
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    pub fn find(&mut self, mut element: usize) -> usize {
+        while self.parents[element] != element {
+            self.parents[element] = self.parents[self.parents[element]];
+            element = self.parents[element];
+        }
+        element
+    }
+
+    // Returns whether two different sets were merged
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.sizes[a] < self.sizes[b] {
+            (a, b) = (b, a);
+        }
+        self.parents[b] = a;
+        self.sizes[a] += self.sizes[b];
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    pub fn set_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.sizes[root]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    pub fn offsets(&self) -> &'static [Vec2<isize>] {
+        match self {
+            Self::Four => &[
+                Vec2 { x: 0, y: -1 },
+                Vec2 { x: 1, y: 0 },
+                Vec2 { x: 0, y: 1 },
+                Vec2 { x: -1, y: 0 },
+            ],
+            Self::Eight => &[
+                Vec2 { x: 0, y: -1 },
+                Vec2 { x: 1, y: -1 },
+                Vec2 { x: 1, y: 0 },
+                Vec2 { x: 1, y: 1 },
+                Vec2 { x: 0, y: 1 },
+                Vec2 { x: -1, y: 1 },
+                Vec2 { x: -1, y: 0 },
+                Vec2 { x: -1, y: -1 },
+            ],
+        }
+    }
+}
+
+// Labels are numbered from 0 in row-major order of the components' first cells
+pub fn label_components<T, F>(
+    grid: &Array2<T>,
+    connectivity: Connectivity,
+    same_component: F,
+) -> (Array2<usize>, usize)
+where
+    F: Fn(&T, &T) -> bool,
+{
+    let (rows, cols) = grid.dim();
+    let flat = |index: Vec2<usize>| index.y * cols + index.x;
+    let mut sets = UnionFind::new(rows * cols);
+    for (index, value) in grid.indexed_iter() {
+        let index = Vec2::from_index_tuple(index);
+        for neighbor in connectivity
+            .offsets()
+            .iter()
+            .filter_map(|offset| index.signed_add(*offset))
+        {
+            if grid
+                .get(neighbor)
+                .is_some_and(|other| same_component(value, other))
+            {
+                sets.union(flat(index), flat(neighbor));
+            }
+        }
+    }
+
+    let mut root_labels = FxHashMap::default();
+    let labels = Array2::from_shape_fn((rows, cols), |(row, col)| {
+        let root = sets.find(row * cols + col);
+        let next_label = root_labels.len();
+        *root_labels.entry(root).or_insert(next_label)
+    });
+    (labels, root_labels.len())
+}
+
+// Components of cells which are activated one at a time, e.g. falling obstacles
+#[derive(Clone, Debug)]
+pub struct IncrementalComponents {
+    cols: usize,
+    connectivity: Connectivity,
+    active: Array2<bool>,
+    sets: UnionFind,
+    components: usize,
+}
+
+impl IncrementalComponents {
+    pub fn new(dim: Vec2<usize>, connectivity: Connectivity) -> Self {
+        Self {
+            cols: dim.x,
+            connectivity,
+            active: Array2::from_elem((dim.y, dim.x), false),
+            sets: UnionFind::new(dim.x * dim.y),
+            components: 0,
+        }
+    }
+
+    // Returns whether the cell was newly activated
+    pub fn add(&mut self, index: Vec2<usize>) -> bool {
+        match self.active.get_mut(index) {
+            Some(active) if !*active => *active = true,
+            _ => return false,
+        }
+        self.components += 1;
+        for neighbor in self
+            .connectivity
+            .offsets()
+            .iter()
+            .filter_map(|offset| index.signed_add(*offset))
+        {
+            if self.is_active(neighbor) && self.sets.union(self.flat(index), self.flat(neighbor)) {
+                self.components -= 1;
+            }
+        }
+        true
+    }
+
+    pub fn is_active(&self, index: Vec2<usize>) -> bool {
+        self.active.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn connected(&mut self, a: Vec2<usize>, b: Vec2<usize>) -> bool {
+        self.is_active(a) && self.is_active(b) && self.sets.connected(self.flat(a), self.flat(b))
+    }
+
+    pub fn component_size(&mut self, index: Vec2<usize>) -> usize {
+        if self.is_active(index) {
+            self.sets.set_size(self.flat(index))
+        } else {
+            0
+        }
+    }
+
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    fn flat(&self, index: Vec2<usize>) -> usize {
+        index.y * self.cols + index.x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: usize, y: usize) -> Vec2<usize> {
+        Vec2 { x, y }
+    }
+
+    #[test]
+    fn union_find() {
+        let mut sets = UnionFind::new(5);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert!(sets.connected(0, 1));
+        assert!(!sets.connected(1, 3));
+        assert!(sets.union(1, 4));
+        assert!(sets.connected(0, 3));
+        assert_eq!(sets.set_size(4), 4);
+        assert_eq!(sets.set_size(2), 1);
+    }
+
+    #[test]
+    fn label_components_connectivity() {
+        // #.#.
+        // .#.#
+        // #.#.
+        let grid = Array2::from_shape_vec((3, 4), "#.#..#.##.#.".chars().collect()).unwrap();
+        let walls = |a: &char, b: &char| a == b;
+
+        let (labels, len) = label_components(&grid, Connectivity::Four, walls);
+        assert_eq!(len, 12);
+        assert_ne!(labels[v(0, 0)], labels[v(1, 1)]);
+
+        // Diagonal walls join, and so do the diagonal gaps between them
+        let (labels, len) = label_components(&grid, Connectivity::Eight, walls);
+        assert_eq!(len, 2);
+        assert_eq!(labels[v(0, 0)], labels[v(2, 2)]);
+        assert_eq!(labels[v(1, 0)], labels[v(3, 2)]);
+        assert_ne!(labels[v(0, 0)], labels[v(1, 0)]);
+    }
+
+    #[test]
+    fn incremental_components() {
+        let mut components = IncrementalComponents::new(v(3, 3), Connectivity::Eight);
+        assert!(components.add(v(0, 0)));
+        assert!(components.add(v(2, 2)));
+        assert_eq!(components.components(), 2);
+        assert!(!components.connected(v(0, 0), v(2, 2)));
+
+        assert!(components.add(v(1, 1)));
+        assert!(!components.add(v(1, 1)));
+        assert!(!components.add(v(3, 0)));
+        assert_eq!(components.components(), 1);
+        assert!(components.connected(v(0, 0), v(2, 2)));
+        assert_eq!(components.component_size(v(2, 2)), 3);
+        assert_eq!(components.component_size(v(0, 1)), 0);
+        assert!(!components.connected(v(0, 0), v(0, 1)));
+    }
+
+    // The first falling byte which cuts the top left corner off the bottom right one,
+    // found by freeing the bytes again in reverse order
+    #[test]
+    fn incremental_components_cut_off() {
+        let bytes = "5,4 4,2 4,5 3,0 2,1 6,3 2,4 1,5 0,6 3,3 2,6 5,1 1,2 5,5 2,5 6,5 1,4 0,4 \
+                     6,4 1,1 6,1 1,0 0,5 1,6 2,0"
+            .split_ascii_whitespace()
+            .map(|byte| {
+                let (x, y) = byte.split_once(',').unwrap();
+                v(x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect::<Vec<_>>();
+        let (start, end) = (v(0, 0), v(6, 6));
+
+        let mut free = IncrementalComponents::new(v(7, 7), Connectivity::Four);
+        for y in 0..7 {
+            for x in 0..7 {
+                if !bytes.contains(&v(x, y)) {
+                    free.add(v(x, y));
+                }
+            }
+        }
+        assert!(!free.connected(start, end));
+        let cut_off = bytes.iter().rev().find(|byte| {
+            free.add(**byte);
+            free.connected(start, end)
+        });
+        assert_eq!(cut_off, Some(&v(6, 1)));
+        assert!(free.components() > 1);
+
+        for byte in &bytes {
+            free.add(*byte);
+        }
+        assert_eq!(free.components(), 1);
+        assert_eq!(free.component_size(start), 49);
+    }
+}
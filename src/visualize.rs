@@ -0,0 +1,282 @@
+#![allow(dead_code)]
+
+use crate::{math::Vec2, AnyError};
+use ndarray::{Array2, Axis};
+use num::ToPrimitive;
+use rustc_hash::FxHashMap;
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write as _},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Self = Self(0, 0, 0);
+    pub const WHITE: Self = Self(255, 255, 255);
+    pub const GREY: Self = Self(90, 90, 90);
+    pub const DARK_GREY: Self = Self(40, 40, 40);
+    pub const RED: Self = Self(230, 60, 60);
+    pub const GREEN: Self = Self(70, 200, 90);
+    pub const BLUE: Self = Self(70, 120, 230);
+    pub const YELLOW: Self = Self(240, 210, 60);
+    pub const ORANGE: Self = Self(240, 140, 40);
+    pub const BROWN: Self = Self(150, 100, 50);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Glyph {
+    pub symbol: char,
+    pub color: Rgb,
+}
+
+impl Glyph {
+    pub const fn new(symbol: char, color: Rgb) -> Self {
+        Self { symbol, color }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Frame {
+    glyphs: Array2<Glyph>,
+}
+
+impl Frame {
+    pub fn new(dim: Vec2<usize>, fill: Glyph) -> Self {
+        Self {
+            glyphs: Array2::from_elem((dim.y, dim.x), fill),
+        }
+    }
+
+    pub fn from_grid<T, F>(grid: &Array2<T>, glyph: F) -> Self
+    where
+        F: Fn(&T) -> Glyph,
+    {
+        Self {
+            glyphs: grid.map(glyph),
+        }
+    }
+
+    pub fn from_points<T, I>(dim: Vec2<usize>, points: I, point: Glyph, background: Glyph) -> Self
+    where
+        T: ToPrimitive,
+        I: IntoIterator<Item = Vec2<T>>,
+    {
+        let mut frame = Self::new(dim, background);
+        for index in points.into_iter().filter_map(|p| p.convert::<usize>()) {
+            frame.set(index, point);
+        }
+        frame
+    }
+
+    pub fn dim(&self) -> Vec2<usize> {
+        Vec2::from_index_tuple(self.glyphs.dim())
+    }
+
+    pub fn get(&self, index: Vec2<usize>) -> Option<&Glyph> {
+        self.glyphs.get(index)
+    }
+
+    pub fn set(&mut self, index: Vec2<usize>, glyph: Glyph) {
+        if let Some(cell) = self.glyphs.get_mut(index) {
+            *cell = glyph;
+        }
+    }
+
+    pub fn to_plain_string(&self) -> String {
+        let mut text = String::new();
+        for row in self.glyphs.axis_iter(Axis(0)) {
+            text.extend(row.iter().map(|glyph| glyph.symbol));
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn to_ansi_string(&self) -> String {
+        let mut text = String::new();
+        for row in self.glyphs.axis_iter(Axis(0)) {
+            let mut color = None;
+            for glyph in row {
+                if color != Some(glyph.color) {
+                    let Rgb(r, g, b) = glyph.color;
+                    write!(text, "\x1b[38;2;{r};{g};{b}m").unwrap();
+                    color = Some(glyph.color);
+                }
+                text.push(glyph.symbol);
+            }
+            text.push_str("\x1b[0m\n");
+        }
+        text
+    }
+
+    pub fn save_png(&self, path: &Path, scale: usize) -> Result<(), AnyError> {
+        let Vec2 {
+            x: width,
+            y: height,
+        } = self.dim() * scale;
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            width.try_into()?,
+            height.try_into()?,
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let pixels = self.pixels(scale, |Rgb(r, g, b)| [r, g, b]);
+        encoder.write_header()?.write_image_data(&pixels.concat())?;
+        Ok(())
+    }
+
+    fn pixels<P, F>(&self, scale: usize, pixel: F) -> Vec<P>
+    where
+        P: Clone,
+        F: Fn(Rgb) -> P,
+    {
+        let Vec2 {
+            x: width,
+            y: height,
+        } = self.dim();
+        let mut pixels = Vec::with_capacity(width * height * scale * scale);
+        for row in self.glyphs.axis_iter(Axis(0)) {
+            let row_pixels = row
+                .iter()
+                .flat_map(|glyph| std::iter::repeat_n(pixel(glyph.color), scale))
+                .collect::<Vec<_>>();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&row_pixels);
+            }
+        }
+        pixels
+    }
+}
+
+// Frames are written to the GIF as they are recorded, only the last one is kept
+pub struct Recorder {
+    gif: Option<GifWriter>,
+    last_frame: Option<Frame>,
+    frame_count: usize,
+    every: usize,
+    steps: usize,
+    // First failure writing the GIF, reported by finish
+    error: Option<AnyError>,
+}
+
+struct GifWriter {
+    path: PathBuf,
+    scale: usize,
+    delay: u16,
+    // Opened with the dimensions of the first frame
+    encoder: Option<(gif::Encoder<BufWriter<File>>, Vec2<usize>)>,
+}
+
+impl Recorder {
+    pub fn new(every: usize) -> Self {
+        Self {
+            gif: None,
+            last_frame: None,
+            frame_count: 0,
+            every: every.max(1),
+            steps: 0,
+            error: None,
+        }
+    }
+
+    pub fn with_gif(mut self, path: &Path, scale: usize, delay: u16) -> Self {
+        self.gif = Some(GifWriter {
+            path: path.to_path_buf(),
+            scale,
+            delay,
+            encoder: None,
+        });
+        self
+    }
+
+    pub fn record(&mut self, frame: Frame) {
+        if let (Some(gif), None) = (&mut self.gif, &self.error) {
+            self.error = gif.write(&frame).err();
+        }
+        self.frame_count += 1;
+        self.last_frame = Some(frame);
+    }
+
+    pub fn record_step<F>(&mut self, render: F)
+    where
+        F: FnOnce() -> Frame,
+    {
+        self.steps += 1;
+        if self.steps.is_multiple_of(self.every) {
+            self.record(render());
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn last_frame(&self) -> Option<&Frame> {
+        self.last_frame.as_ref()
+    }
+
+    // Completes the GIF file
+    pub fn finish(&mut self) -> Result<(), AnyError> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        if let Some((encoder, _)) = self.gif.as_mut().and_then(|gif| gif.encoder.take()) {
+            encoder.into_inner()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl GifWriter {
+    // Each frame carries its own palette, as the colors of later frames aren't known yet
+    fn write(&mut self, frame: &Frame) -> Result<(), AnyError> {
+        let Vec2 {
+            x: width,
+            y: height,
+        } = frame.dim() * self.scale;
+        let (encoder, dim) = match &mut self.encoder {
+            Some(encoder) => encoder,
+            None => {
+                let mut encoder = gif::Encoder::new(
+                    BufWriter::new(File::create(&self.path)?),
+                    width.try_into()?,
+                    height.try_into()?,
+                    &[],
+                )?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                self.encoder.insert((encoder, frame.dim()))
+            }
+        };
+        if frame.dim() != *dim {
+            return Err("Frames have different dimensions".into());
+        }
+
+        let mut color_indices: FxHashMap<Rgb, u8> = FxHashMap::default();
+        let mut palette = Vec::new();
+        for glyph in &frame.glyphs {
+            if !color_indices.contains_key(&glyph.color) {
+                let index = u8::try_from(color_indices.len())
+                    .map_err(|_| "Too many colors for a GIF palette")?;
+                color_indices.insert(glyph.color, index);
+                let Rgb(r, g, b) = glyph.color;
+                palette.extend([r, g, b]);
+            }
+        }
+        let gif_frame = gif::Frame {
+            width: width.try_into()?,
+            height: height.try_into()?,
+            delay: self.delay,
+            palette: Some(palette),
+            buffer: frame
+                .pixels(self.scale, |color| color_indices[&color])
+                .into(),
+            ..Default::default()
+        };
+        encoder.write_frame(&gif_frame)?;
+        Ok(())
+    }
+}
@@ -1,66 +1,251 @@
-// use indexset::BTreeMap;
-use crate::{get_text_file, SolutionResult};
+use crate::{
+    get_text_file,
+    math::{checked_product, to_answer, try_checked_sum, Answer},
+    AnyError, SolutionResult,
+};
 use itertools::Itertools;
-use rustc_hash::FxHashMap;
 use std::{
+    fmt::Write,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, Read},
 };
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/1/input";
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let mut ids_1: Vec<i64> = Vec::new();
-    let mut ids_2: Vec<i64> = Vec::new();
+const TOP_SIMILAR_IDS: usize = 5;
+
+// Both location id columns, each sorted
+struct IdLists {
+    left: Vec<i64>,
+    right: Vec<i64>,
+}
 
-    for (id_1, id_2) in iter_input(file) {
-        ids_1.push(id_1);
-        ids_2.push(id_2);
+// Byte at a time parser of a line of two whitespace separated ids
+#[derive(Default)]
+struct LineParser {
+    line: usize,
+    ids: [i64; 2],
+    columns: usize,
+    number: Option<i64>,
+    negative: bool,
+}
+
+// An id with how often it occurs in each list
+struct SharedId {
+    id: i64,
+    left: usize,
+    right: usize,
+}
+
+impl LineParser {
+    // Returns the ids when the byte ends a non-empty line
+    fn push(&mut self, byte: u8) -> Result<Option<[i64; 2]>, AnyError> {
+        match byte {
+            b'0'..=b'9' => {
+                self.number = self
+                    .number
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|number| number.checked_add(i64::from(byte - b'0')));
+                if self.number.is_none() {
+                    return Err(format!("Id too large on line {}", self.line + 1).into());
+                }
+            }
+            b'-' if self.number.is_none() && !self.negative => self.negative = true,
+            b' ' | b'\t' | b'\r' => self.end_number()?,
+            b'\n' => return self.end_line(),
+            _ => {
+                return Err(format!(
+                    "Unexpected character {:?} on line {}",
+                    char::from(byte),
+                    self.line + 1
+                )
+                .into())
+            }
+        }
+        Ok(None)
     }
-    ids_1.sort_unstable();
-    ids_2.sort_unstable();
 
-    let distance: i64 = ids_1
-        .iter()
-        .zip(ids_2.iter())
-        .map(|(id_1, id_2)| (id_1 - id_2).abs())
-        .sum();
+    fn end_number(&mut self) -> Result<(), AnyError> {
+        let Some(number) = self.number.take() else {
+            if self.negative {
+                return Err(format!("Dangling minus sign on line {}", self.line + 1).into());
+            }
+            return Ok(());
+        };
+        let id = self
+            .ids
+            .get_mut(self.columns)
+            .ok_or_else(|| format!("More than two ids on line {}", self.line + 1))?;
+        *id = if std::mem::take(&mut self.negative) {
+            -number
+        } else {
+            number
+        };
+        self.columns += 1;
+        Ok(())
+    }
 
-    Ok(distance.to_string())
+    fn end_line(&mut self) -> Result<Option<[i64; 2]>, AnyError> {
+        self.end_number()?;
+        self.line += 1;
+        match std::mem::take(&mut self.columns) {
+            0 => Ok(None),
+            2 => Ok(Some(self.ids)),
+            _ => Err(format!("Missing id on line {}", self.line).into()),
+        }
+    }
 }
 
-pub fn part_2() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
+impl IdLists {
+    // Streams through the reader's buffer without allocating per line
+    fn parse(mut reader: impl BufRead) -> Result<Self, AnyError> {
+        let mut parser = LineParser::default();
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        loop {
+            let buffer = reader.fill_buf()?;
+            if buffer.is_empty() {
+                break;
+            }
+            for byte in buffer {
+                if let Some([id_1, id_2]) = parser.push(*byte)? {
+                    left.push(id_1);
+                    right.push(id_2);
+                }
+            }
+            let len = buffer.len();
+            reader.consume(len);
+        }
+        if let Some([id_1, id_2]) = parser.end_line()? {
+            left.push(id_1);
+            right.push(id_2);
+        }
+        left.sort_unstable();
+        right.sort_unstable();
+        Ok(Self { left, right })
+    }
+
+    fn distances(&self) -> impl Iterator<Item = u64> + '_ {
+        self.left
+            .iter()
+            .zip(&self.right)
+            .map(|(id_1, id_2)| id_1.abs_diff(*id_2))
+    }
 
-    let mut id_map_1: FxHashMap<i64, usize> = FxHashMap::default();
-    let mut id_map_2: FxHashMap<i64, usize> = FxHashMap::default();
+    fn total_distance(&self) -> Result<Answer, AnyError> {
+        try_checked_sum(self.distances().map(to_answer))
+    }
 
-    for (id_1, id_2) in iter_input(file) {
-        insert_id_fxhashmap(&mut id_map_1, id_1);
-        insert_id_fxhashmap(&mut id_map_2, id_2);
+    // Lower median for an even number of pairs
+    fn median_distance(&self) -> Option<u64> {
+        let mut distances = self.distances().collect_vec();
+        let middle = distances.len().checked_sub(1)? / 2;
+        Some(*distances.select_nth_unstable(middle).1)
     }
 
-    let similarity: i64 = id_map_1
-        .iter()
-        .filter_map(|(id, occ_1)| {
-            id_map_2.get(id).map(|occ_2| *id * *occ_1 as i64 * *occ_2 as i64)
-        })
-        .sum();
+    // Merges the runs of equal ids of both sorted lists
+    fn shared_ids(&self) -> impl Iterator<Item = SharedId> + '_ {
+        let mut right = self.right.iter().dedup_with_count().peekable();
+        self.left
+            .iter()
+            .dedup_with_count()
+            .filter_map(move |(left, id)| {
+                while right.next_if(|(_, other)| *other < id).is_some() {}
+                let (right, _) = right.next_if(|(_, other)| *other == id)?;
+                Some(SharedId {
+                    id: *id,
+                    left,
+                    right,
+                })
+            })
+    }
+
+    fn similarity(&self) -> Result<Answer, AnyError> {
+        try_checked_sum(self.shared_ids().map(|shared| shared.score()))
+    }
 
-    Ok(similarity.to_string())
+    fn most_similar(&self, count: usize) -> Result<Vec<(SharedId, Answer)>, AnyError> {
+        let mut scored = self
+            .shared_ids()
+            .map(|shared| {
+                let score = shared.score()?;
+                Ok((shared, score))
+            })
+            .collect::<Result<Vec<_>, AnyError>>()?;
+        scored.sort_by(|(a, score_a), (b, score_b)| score_b.cmp(score_a).then(a.id.cmp(&b.id)));
+        scored.truncate(count);
+        Ok(scored)
+    }
+}
+
+impl SharedId {
+    fn score(&self) -> Result<Answer, AnyError> {
+        checked_product([
+            to_answer(self.id)?,
+            to_answer(self.left)?,
+            to_answer(self.right)?,
+        ])
+    }
+}
+
+pub fn part_1() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let id_lists = read_input(file)?;
+
+    Ok(id_lists.total_distance()?.to_string())
+}
+
+pub fn part_2() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let id_lists = read_input(file)?;
+
+    Ok(id_lists.similarity()?.to_string())
+}
+
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let id_lists = read_input(file)?;
+
+    let mut explanation = String::new();
+    match part {
+        1 => writeln!(
+            explanation,
+            "{} pairs, total distance {}, median distance {}",
+            id_lists.left.len(),
+            id_lists.total_distance()?,
+            id_lists
+                .median_distance()
+                .map_or("-".to_string(), |median| median.to_string())
+        )?,
+        2 => {
+            for (shared, score) in id_lists.most_similar(TOP_SIMILAR_IDS)? {
+                writeln!(
+                    explanation,
+                    "{}: {} times left, {} times right, similarity {score}",
+                    shared.id, shared.left, shared.right
+                )?;
+            }
+        }
+        _ => return Err("Part does not exist".into()),
+    }
+    Ok(explanation)
 }
 
-fn iter_input(file: File) -> impl Iterator<Item = (i64, i64)> {
-    BufReader::new(file).lines().map(|line| {
-        let line = line.unwrap();
-        line.split_ascii_whitespace()
-            .map(|ch| ch.parse().unwrap())
-            .collect_tuple()
-            .unwrap()
-    })
+// Both answers and the extra statistics of a whole input
+pub fn list_statistics(reader: impl Read) -> SolutionResult {
+    let id_lists = IdLists::parse(BufReader::new(reader))?;
+    let median = id_lists.median_distance().unwrap_or(0);
+    let top_id = id_lists
+        .most_similar(1)?
+        .first()
+        .map(|(shared, _)| shared.id);
+    Ok(format!(
+        "{} {} {median} {top_id:?}",
+        id_lists.total_distance()?,
+        id_lists.similarity()?
+    ))
 }
 
-fn insert_id_fxhashmap(id_set: &mut FxHashMap<i64, usize>, id: i64) {
-    id_set.entry(id).and_modify(|curr| *curr += 1).or_insert(1);
+fn read_input(file: File) -> Result<IdLists, AnyError> {
+    IdLists::parse(BufReader::new(file))
 }
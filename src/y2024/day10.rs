@@ -1,8 +1,14 @@
-use crate::{get_text_file, math::Vec2, SolutionResult};
+use crate::{
+    get_text_file,
+    math::{checked_sum, to_answer, try_checked_sum, Answer, OrOverflow, Vec2},
+    AnyError, SolutionResult,
+};
 use itertools::Itertools;
 use ndarray::Array2;
+use num::{CheckedAdd, One, Zero};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
+    fmt::Write,
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -12,113 +18,200 @@ const INPUT_URL: &str = "https://adventofcode.com/2024/day/10/input";
 const TRAILHEAD: i32 = 0;
 const TRAILTAIL: i32 = 9;
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
+const MAX_EXPLAINED_PATHS: usize = 3;
+
+#[derive(Clone, Debug)]
+struct Trailhead {
+    position: Vec2<usize>,
+    tails: Vec<Vec2<usize>>,
+    rating: Answer,
+    // At most the requested number of paths
+    paths: Vec<Vec<Vec2<usize>>>,
+}
+
+// Trails climb one height per step from the head height to the tail height, which may
+// also lie below the head
+struct TrailMap {
+    heights: Array2<i32>,
+    head: i32,
+    tail: i32,
+    step: i32,
+    // Per cell, the tails and the number of distinct trails reachable from it
+    tails: Array2<FxHashSet<Vec2<usize>>>,
+    paths: Array2<Answer>,
+}
 
-    let (topographic_map, trailheads) = read_input(file);
-    let trail_seqence: FxHashMap<_, _> = (TRAILHEAD..=TRAILTAIL).tuple_windows().collect();
-
-    let score: usize = trailheads
-        .into_iter()
-        .map(|trailhead| {
-            let mut trail_item = TRAILHEAD;
-            let mut positions = FxHashSet::default();
-            positions.insert(trailhead);
-            while !positions.is_empty() {
-                positions = FxHashSet::from_iter(positions.into_iter().flat_map(|position| {
-                    iter_neighbors(position).filter(|neighbor| {
-                        if let Some(neighbor_item) = topographic_map.get(*neighbor) {
-                            trail_seqence[&trail_item] == *neighbor_item
-                        } else {
-                            false
-                        }
-                    })
-                }));
-
-                trail_item = trail_seqence[&trail_item];
-                if trail_item == TRAILTAIL {
-                    return positions.len();
+impl TrailMap {
+    fn new(heights: Array2<i32>, head: i32, tail: i32) -> Result<Self, AnyError> {
+        let step = (tail - head).signum();
+        let mut levels: FxHashMap<i32, Vec<Vec2<usize>>> = FxHashMap::default();
+        for (index, height) in heights.indexed_iter() {
+            levels
+                .entry(*height)
+                .or_default()
+                .push(Vec2::from_index_tuple(index));
+        }
+
+        let mut tails = Array2::from_elem(heights.dim(), FxHashSet::default());
+        let mut paths = Array2::from_elem(heights.dim(), Answer::zero());
+        let num_levels = if step == 0 { 0 } else { (tail - head) / step };
+        for height in (0..=num_levels).map(|level| tail - level * step) {
+            for position in levels.get(&height).into_iter().flatten() {
+                if height == tail {
+                    tails[*position] = FxHashSet::from_iter([*position]);
+                    paths[*position] = Answer::one();
+                    continue;
+                }
+                let mut reachable = FxHashSet::default();
+                let mut count = Answer::zero();
+                for neighbor in iter_neighbors(*position)
+                    .filter(|neighbor| heights.get(*neighbor) == Some(&(height + step)))
+                {
+                    reachable.extend(tails[neighbor].iter().copied());
+                    count = CheckedAdd::checked_add(&count, &paths[neighbor]).or_overflow()?;
                 }
+                tails[*position] = reachable;
+                paths[*position] = count;
             }
-            0
+        }
+
+        Ok(Self {
+            heights,
+            head,
+            tail,
+            step,
+            tails,
+            paths,
         })
-        .sum();
+    }
+
+    fn trailheads(&self, max_paths: usize) -> Vec<Trailhead> {
+        self.heights
+            .indexed_iter()
+            .filter(|(_, height)| **height == self.head)
+            .map(|(index, _)| {
+                let position = Vec2::from_index_tuple(index);
+                let mut paths = Vec::new();
+                self.collect_paths(position, &mut Vec::new(), &mut paths, max_paths);
+                Trailhead {
+                    position,
+                    tails: self.tails[position]
+                        .iter()
+                        .copied()
+                        .sorted_by_key(|tail| (tail.y, tail.x))
+                        .collect(),
+                    rating: self.paths[position].to_owned(),
+                    paths,
+                }
+            })
+            .collect()
+    }
+
+    // Only follows cells with a trail count, so no branch is a dead end
+    fn collect_paths(
+        &self,
+        position: Vec2<usize>,
+        path: &mut Vec<Vec2<usize>>,
+        paths: &mut Vec<Vec<Vec2<usize>>>,
+        max_paths: usize,
+    ) {
+        if paths.len() >= max_paths || self.paths[position].is_zero() {
+            return;
+        }
+        path.push(position);
+        let height = self.heights[position];
+        if height == self.tail {
+            paths.push(path.clone());
+        } else {
+            for neighbor in iter_neighbors(position)
+                .filter(|neighbor| self.heights.get(*neighbor) == Some(&(height + self.step)))
+            {
+                self.collect_paths(neighbor, path, paths, max_paths);
+            }
+        }
+        path.pop();
+    }
+}
+
+pub fn part_1() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let trail_map = TrailMap::new(read_input(file), TRAILHEAD, TRAILTAIL)?;
+
+    let score = try_checked_sum(
+        trail_map
+            .trailheads(0)
+            .into_iter()
+            .map(|trailhead| to_answer(trailhead.tails.len())),
+    )?;
 
     Ok(score.to_string())
 }
 
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
+    let trail_map = TrailMap::new(read_input(file), TRAILHEAD, TRAILTAIL)?;
 
-    let (topographic_map, trailheads) = read_input(file);
-    let trail_seqence: FxHashMap<_, _> = (TRAILHEAD..=TRAILTAIL).tuple_windows().collect();
-
-    let rating: usize = trailheads
-        .into_iter()
-        .map(|trailhead| {
-            let mut trail_item = TRAILHEAD;
-
-            let mut positions = FxHashSet::default();
-            positions.insert(trailhead);
-
-            let mut position_paths = FxHashMap::default();
-            position_paths.insert(trailhead, 1);
-
-            while !positions.is_empty() {
-                positions = FxHashSet::from_iter(positions.into_iter().flat_map(|node| {
-                    iter_neighbors(node)
-                        .filter(|neighbor| {
-                            if let Some(neighbor_item) = topographic_map.get(*neighbor) {
-                                if trail_seqence[&trail_item] == *neighbor_item {
-                                    let node_paths = position_paths[&node];
-                                    position_paths
-                                        .entry(*neighbor)
-                                        .and_modify(|p| *p += node_paths)
-                                        .or_insert(node_paths);
-                                    return true;
-                                }
-                            }
-                            false
-                        })
-                        .collect_vec()
-                }));
-
-                trail_item = trail_seqence[&trail_item];
-                if trail_item == TRAILTAIL {
-                    return positions.iter().map(|node| position_paths[node]).sum();
-                }
-            }
-            0
-        })
-        .sum();
+    let rating = checked_sum(
+        trail_map
+            .trailheads(0)
+            .into_iter()
+            .map(|trailhead| trailhead.rating),
+    )?;
 
     Ok(rating.to_string())
 }
 
-fn read_input(file: File) -> (Array2<i32>, Vec<Vec2<usize>>) {
-    let mut positions = Vec::new();
-    let mut trailtails = Vec::new();
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let trail_map = TrailMap::new(read_input(file), TRAILHEAD, TRAILTAIL)?;
+
+    let mut explanation = String::new();
+    for trailhead in trail_map.trailheads(MAX_EXPLAINED_PATHS) {
+        let Vec2 { x, y } = trailhead.position;
+        match part {
+            1 => writeln!(
+                explanation,
+                "({x}, {y}): score {}, tails {}",
+                trailhead.tails.len(),
+                trailhead
+                    .tails
+                    .iter()
+                    .map(|tail| format!("({}, {})", tail.x, tail.y))
+                    .join(" ")
+            )?,
+            _ => {
+                writeln!(explanation, "({x}, {y}): rating {}", trailhead.rating)?;
+                for path in trailhead.paths {
+                    writeln!(
+                        explanation,
+                        "  {}",
+                        path.iter()
+                            .map(|position| format!("({}, {})", position.x, position.y))
+                            .join(" ")
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(explanation)
+}
+
+fn read_input(file: File) -> Array2<i32> {
+    let mut heights = Vec::new();
 
     let rows = BufReader::new(file)
         .lines()
-        .enumerate()
-        .map(|(row, line)| {
-            positions.extend(line.unwrap().chars().enumerate().filter_map(|(col, ch)| {
-                ch.to_digit(10).map(|val| {
-                    if val == TRAILHEAD as u32 {
-                        trailtails.push(Vec2 { x: col, y: row });
-                    }
-                    val as i32
-                })
-            }));
+        .map(|line| {
+            heights.extend(
+                line.unwrap()
+                    .chars()
+                    .filter_map(|ch| ch.to_digit(10).map(|val| val as i32)),
+            );
         })
         .count();
 
-    let cols = positions.len() / rows;
-    (
-        Array2::from_shape_vec((rows, cols), positions).unwrap(),
-        trailtails,
-    )
+    let cols = heights.len() / rows;
+    Array2::from_shape_vec((rows, cols), heights).unwrap()
 }
 
 fn iter_neighbors(idx: Vec2<usize>) -> impl Iterator<Item = Vec2<usize>> {
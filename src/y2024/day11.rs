@@ -1,7 +1,7 @@
-use crate::{get_text_file, SolutionResult};
-use itertools::Itertools;
-use rustc_hash::{FxHashMap, FxHashSet};
-use std::{collections::BTreeMap, error::Error, fs::File, io::Read, iter, vec};
+use crate::{get_text_file, math::Answer, AnyError, SolutionResult};
+use num::{CheckedAdd, One, Zero};
+use rustc_hash::FxHashMap;
+use std::io::Read;
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/11/input";
 
@@ -9,243 +9,154 @@ const NUM_BLINKS_1: usize = 25;
 const NUM_BLINKS_2: usize = 75;
 const SCALE_FACTOR: i64 = 2024;
 
-#[derive(Debug, Clone)]
-struct ComposedNodes(FxHashMap<(i64, usize), usize>);
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Blink {
+    Replace(i64),
+    Split(i64, i64),
+}
 
-impl ComposedNodes {
-    fn insert(&mut self, node: i64, offset: usize, quantity: usize) {
-        self.0
-            .entry((node, offset))
-            .and_modify(|q| *q += quantity)
-            .or_insert(quantity);
-    }
+trait StoneRules {
+    // None when the new stone does not fit in an i64
+    fn blink(&self, stone: i64) -> Option<Blink>;
+}
 
-    fn add_offsets(&mut self, amount: usize) {
-        self.0 = self
-            .0
-            .drain()
-            .map(|((node, offset), quantity)| ((node, offset + amount), quantity))
-            .collect();
-    }
+struct PuzzleRules {
+    scale_factor: i64,
+}
 
-    fn multiply_quantities(&mut self, factor: usize) {
-        for (_, quantity_) in &mut self.0 {
-            *quantity_ *= factor;
+impl StoneRules for PuzzleRules {
+    fn blink(&self, stone: i64) -> Option<Blink> {
+        if stone == 0 {
+            return Some(Blink::Replace(1));
         }
-    }
-
-    fn merge(&mut self, others: Vec<Self>) {
-        for ((node, offset), quantity) in others.into_iter().flat_map(|c| c.0) {
-            self.insert(node, offset, quantity);
+        let digits = num_digits(stone);
+        if digits.is_multiple_of(2) {
+            let half = 10_i64.pow(digits / 2);
+            Some(Blink::Split(stone / half, stone % half))
+        } else {
+            stone.checked_mul(self.scale_factor).map(Blink::Replace)
         }
     }
 }
 
-#[derive(Debug)]
-struct NodeTree {
-    blink_counts: Vec<usize>,
-    leaves: ComposedNodes,
+struct StoneCounter<R, C> {
+    rules: R,
+    cache: FxHashMap<(i64, usize), C>,
 }
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let mut stones = read_input(file)?;
-    for _ in 0..NUM_BLINKS_1 {
-        let stones_len = stones.len();
-        for idx in 0..stones_len {
-            let n = &mut stones[idx];
-            if *n == 0 {
-                *n = 1;
-            } else {
-                let digits = get_digits(*n);
-                if digits.len() % 2 == 0 {
-                    let mid = digits.len() / 2;
-                    *n = digit_to_value(&digits[mid..]);
-                    stones.push(digit_to_value(&digits[..mid]));
-                } else {
-                    *n *= SCALE_FACTOR;
-                }
-            }
+impl<R, C> StoneCounter<R, C>
+where
+    R: StoneRules,
+    C: CheckedAdd + Zero + One + Clone,
+{
+    fn new(rules: R) -> Self {
+        Self {
+            rules,
+            cache: FxHashMap::default(),
         }
     }
-    Ok(stones.len().to_string())
-}
 
-pub fn part_2() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let stones = read_input(file)?;
-
-    let nodes = (0..=9).collect_vec();
-    let node_trees = compute_node_trees(&nodes);
-
-    let node_depth_table = compute_node_depth_table(&nodes, &node_trees);
-
-    let mut num_stones = 0;
-    for root in stones {
-        let mut sub_stones = vec![root];
-        for num_blinks in (1..=NUM_BLINKS_2).rev() {
-            sub_stones = sub_stones
-                .into_iter()
-                .filter(|node| {
-                    get_node_num_stones(&node_trees, &node_depth_table, *node, num_blinks)
-                        .inspect(|num| num_stones += num)
-                        .is_none()
-                })
-                .flat_map(|node| blink(node))
-                .collect();
-            if sub_stones.is_empty() {
-                break;
-            }
+    fn count(&mut self, stone: i64, blinks: usize) -> Result<C, AnyError> {
+        if blinks == 0 {
+            return Ok(C::one());
         }
-        if !sub_stones.is_empty() {
-            num_stones += sub_stones.len();
+        if let Some(count) = self.cache.get(&(stone, blinks)) {
+            return Ok(count.clone());
         }
+        let count = match self
+            .rules
+            .blink(stone)
+            .ok_or(format!("Stone {stone} overflowed after blinking"))?
+        {
+            Blink::Replace(stone) => self.count(stone, blinks - 1)?,
+            Blink::Split(left, right) => self
+                .count(left, blinks - 1)?
+                .checked_add(&self.count(right, blinks - 1)?)
+                .ok_or("Stone count overflowed")?,
+        };
+        self.cache.insert((stone, blinks), count.clone());
+        Ok(count)
     }
 
-    Ok(num_stones.to_string())
+    fn count_all(&mut self, stones: &[i64], blinks: usize) -> Result<C, AnyError> {
+        stones.iter().try_fold(C::zero(), |total, stone| {
+            total
+                .checked_add(&self.count(*stone, blinks)?)
+                .ok_or_else(|| "Stone count overflowed".into())
+        })
+    }
 }
 
-fn read_input(mut file: File) -> Result<Vec<i64>, Box<dyn Error>> {
+pub fn part_1() -> SolutionResult {
     let mut text = String::new();
-    file.read_to_string(&mut text)?;
-    Ok(text
-        .split_ascii_whitespace()
-        .map(|ch| ch.parse().unwrap())
-        .collect())
+    get_text_file(INPUT_URL)?.read_to_string(&mut text)?;
+
+    stone_count(&text, NUM_BLINKS_1)
 }
 
-fn compute_node_trees(nodes: &[i64]) -> FxHashMap<i64, NodeTree> {
-    let nodes: FxHashSet<_> = nodes.into_iter().collect();
-    nodes
-        .iter()
-        .map(|root| {
-            let root = **root;
-            let mut stones = vec![(root, false)];
-            let mut blink_counts = vec![1];
-            let mut leaves = ComposedNodes(Default::default());
-            while !stones.is_empty() {
-                leaves.add_offsets(1);
-
-                stones = stones
-                    .into_iter()
-                    .flat_map(|(n, ignore)| blink(n).into_iter().map(move |n| (n, ignore)))
-                    .collect();
-
-                for (n, ignore) in &mut stones {
-                    if !*ignore && nodes.contains(n) {
-                        leaves.insert(*n, 0, 1);
-                        *ignore = true;
-                    }
-                }
-                if stones.iter().all(|(_, ignore)| *ignore) {
-                    break;
-                }
-                blink_counts.push(stones.len());
-            }
-            (
-                root,
-                NodeTree {
-                    blink_counts,
-                    leaves,
-                },
-            )
-        })
-        .collect()
+pub fn part_2() -> SolutionResult {
+    let mut text = String::new();
+    get_text_file(INPUT_URL)?.read_to_string(&mut text)?;
+
+    stone_count(&text, NUM_BLINKS_2)
 }
 
-fn compute_node_depth_table(
-    nodes: &[i64],
-    node_trees: &FxHashMap<i64, NodeTree>,
-) -> BTreeMap<i64, Vec<ComposedNodes>> {
-    let mut node_depth_table: BTreeMap<i64, Vec<ComposedNodes>> =
-        nodes.iter().map(|b| (*b, Vec::new())).collect();
-
-    for _ in 0..=NUM_BLINKS_2 {
-        for (node, composed_nodes_vec) in &mut node_depth_table {
-            if let Some(mut composed_nodes) = composed_nodes_vec.last().cloned() {
-                composed_nodes.add_offsets(1);
-
-                let mut sub_composed_nodes_vec = Vec::new();
-                let mut to_remove = Vec::new();
-
-                for ((sub_node, offset), quantity) in &mut composed_nodes.0 {
-                    let sub_node_tree = &node_trees[sub_node];
-                    let depth = sub_node_tree.blink_counts.len();
-                    if *offset == depth {
-                        let mut leaves = sub_node_tree.leaves.clone();
-                        leaves.multiply_quantities(*quantity);
-                        sub_composed_nodes_vec.push(leaves);
-                        to_remove.push((*sub_node, *offset));
-                    }
-                    assert!(*offset <= depth);
-                }
-                for key in &to_remove {
-                    composed_nodes.0.remove(key);
-                }
-                composed_nodes.merge(sub_composed_nodes_vec);
-                composed_nodes_vec.push(composed_nodes);
-            } else {
-                composed_nodes_vec.push(ComposedNodes(iter::once(((*node, 0), 1)).collect()));
-            }
-        }
-    }
-    node_depth_table
+// Number of stones after any number of blinks, large counts need the u128-answers or
+// bigint-answers feature
+pub fn stone_count(stones: &str, blinks: usize) -> SolutionResult {
+    let stones = parse_stones(stones)?;
+    let mut counter: StoneCounter<_, Answer> = StoneCounter::new(PuzzleRules {
+        scale_factor: SCALE_FACTOR,
+    });
+    let num_stones = counter.count_all(&stones, blinks)?;
+
+    Ok(num_stones.to_string())
 }
 
-fn get_node_num_stones(
-    node_trees: &FxHashMap<i64, NodeTree>,
-    node_depth_table: &BTreeMap<i64, Vec<ComposedNodes>>,
-    node: i64,
-    num_blinks: usize,
-) -> Option<usize> {
-    let composed_nodes = node_depth_table.get(&node)?.get(num_blinks)?;
-    Some(
-        composed_nodes
-            .0
-            .iter()
-            .map(|((sub_node, offset), quantity)| {
-                let sub_node_tree = &node_trees[sub_node];
-                quantity * sub_node_tree.blink_counts[*offset]
-            })
-            .sum(),
-    )
+fn parse_stones(text: &str) -> Result<Vec<i64>, AnyError> {
+    Ok(text
+        .split_ascii_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()?)
 }
 
-fn blink(n: i64) -> Vec<i64> {
-    if n == 0 {
-        vec![1]
-    } else {
-        let digits = get_digits(n);
-        // println!("digits {:?}", digits);
-        if digits.len() % 2 == 0 {
-            let mid = digits.len() / 2;
-            vec![
-                digit_to_value(&digits[mid..]),
-                digit_to_value(&digits[..mid]),
-            ]
-        } else {
-            vec![n * SCALE_FACTOR]
-        }
-    }
+fn num_digits(n: i64) -> u32 {
+    n.unsigned_abs().checked_ilog10().unwrap_or(0) + 1
 }
 
-fn get_digits(n: i64) -> Vec<i64> {
-    let mut num = n.abs();
-    if num == 0 {
-        return vec![0];
-    }
-    let mut digits = Vec::new();
-    while num > 0 {
-        digits.push(num % 10);
-        num /= 10;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "125 17";
+
+    #[test]
+    fn puzzle_blinks() {
+        assert_eq!(stone_count(EXAMPLE, NUM_BLINKS_1).unwrap(), "55312");
+        assert_eq!(
+            stone_count(EXAMPLE, NUM_BLINKS_2).unwrap(),
+            "65601038650482"
+        );
     }
-    digits
-}
 
-fn digit_to_value(digits: &[i64]) -> i64 {
-    digits
-        .iter()
-        .enumerate()
-        .map(|(idx, d)| d * 10_i64.pow(idx as u32))
-        .sum()
+    #[test]
+    fn many_blinks() {
+        let count = stone_count(EXAMPLE, 200);
+        #[cfg(any(feature = "u128-answers", feature = "bigint-answers"))]
+        assert_eq!(count.unwrap(), "3228697720950807773236428359413636851");
+        #[cfg(not(any(feature = "u128-answers", feature = "bigint-answers")))]
+        assert_eq!(count.unwrap_err().to_string(), "Stone count overflowed");
+
+        // 182 digits
+        let count = stone_count(EXAMPLE, 1000);
+        #[cfg(feature = "bigint-answers")]
+        {
+            let count = count.unwrap();
+            assert_eq!(count.len(), 182);
+            assert!(count.starts_with("54741524973376212565"));
+            assert!(count.ends_with("674552924"));
+        }
+        #[cfg(not(feature = "bigint-answers"))]
+        assert_eq!(count.unwrap_err().to_string(), "Stone count overflowed");
+    }
 }
@@ -1,5 +1,4 @@
 use std::{
-    cmp::Ordering,
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -7,7 +6,12 @@ use std::{
 use itertools::Itertools;
 use regex::Regex;
 
-use crate::{get_text_file, math::Vec2, SolutionResult};
+use crate::{
+    get_text_file,
+    math::{chinese_remainder, to_answer, try_checked_product, Vec2},
+    visualize::{Frame, Glyph, Recorder, Rgb},
+    AnyError, SolutionResult,
+};
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/14/input";
 
@@ -16,6 +20,10 @@ const AREA_HEIGHT: i64 = 103;
 // const AREA_WIDTH: i64 = 11;
 // const AREA_HEIGHT: i64 = 7;
 const ELAPSED: i64 = 100;
+const VISUALIZE_LEAD_UP: i64 = 20;
+
+const EMPTY: char = '.';
+const ROBOT: char = '#';
 
 #[derive(Clone, Debug)]
 struct Robot {
@@ -23,8 +31,93 @@ struct Robot {
     velocity: Vec2<i64>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TreeDetector {
+    MinVariance,
+    MinSafetyFactor,
+    LongestRun,
+}
+
 pub fn part_1() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
+    let robots = read_input(file);
+
+    let safety = try_checked_product(
+        quadrant_counts(&positions_at(&robots, ELAPSED))
+            .into_iter()
+            .map(to_answer),
+    )?;
+
+    Ok(safety.to_string())
+}
+
+pub fn part_2() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let robots = read_input(file);
+
+    let elapsed = find_tree(&robots, TreeDetector::MinVariance).ok_or("Failed to solve")?;
+    Ok(elapsed.to_string())
+}
+
+pub fn visualize(recorder: &mut Recorder) -> Result<(), AnyError> {
+    let file = get_text_file(INPUT_URL)?;
+    let robots = read_input(file);
+
+    let tree_elapsed = find_tree(&robots, TreeDetector::MinVariance).ok_or("Failed to solve")?;
+    for elapsed in (tree_elapsed - VISUALIZE_LEAD_UP).max(0)..=tree_elapsed {
+        recorder.record_step(|| render_positions(&positions_at(&robots, elapsed)));
+    }
+    for detector in [TreeDetector::MinSafetyFactor, TreeDetector::LongestRun] {
+        if let Some(elapsed) = find_tree(&robots, detector).filter(|e| *e != tree_elapsed) {
+            recorder.record(render_positions(&positions_at(&robots, elapsed)));
+        }
+    }
+    recorder.record(render_positions(&positions_at(&robots, tree_elapsed)));
+    Ok(())
+}
+
+fn find_tree(robots: &[Robot], detector: TreeDetector) -> Option<i64> {
+    match detector {
+        TreeDetector::MinVariance => {
+            // x and y positions repeat independently every AREA_WIDTH and AREA_HEIGHT seconds
+            let elapsed_x = (0..AREA_WIDTH).min_by_key(|elapsed| {
+                variance_scaled(robots.iter().map(|robot| {
+                    (robot.position.x + robot.velocity.x * elapsed).rem_euclid(AREA_WIDTH)
+                }))
+            })?;
+            let elapsed_y = (0..AREA_HEIGHT).min_by_key(|elapsed| {
+                variance_scaled(robots.iter().map(|robot| {
+                    (robot.position.y + robot.velocity.y * elapsed).rem_euclid(AREA_HEIGHT)
+                }))
+            })?;
+            chinese_remainder(&[(elapsed_x, AREA_WIDTH), (elapsed_y, AREA_HEIGHT)])
+                .map(|(elapsed, _)| elapsed)
+        }
+        TreeDetector::MinSafetyFactor => (0..AREA_WIDTH * AREA_HEIGHT).min_by_key(|elapsed| {
+            quadrant_counts(&positions_at(robots, *elapsed))
+                .into_iter()
+                .map(|count| count as u128)
+                .product::<u128>()
+        }),
+        TreeDetector::LongestRun => (0..AREA_WIDTH * AREA_HEIGHT)
+            .max_by_key(|elapsed| (longest_run(positions_at(robots, *elapsed)), -elapsed)),
+    }
+}
+
+fn positions_at(robots: &[Robot], elapsed: i64) -> Vec<Vec2<i64>> {
+    robots
+        .iter()
+        .map(|Robot { position, velocity }| {
+            let new_position = *position + *velocity * elapsed;
+            Vec2 {
+                x: new_position.x.rem_euclid(AREA_WIDTH),
+                y: new_position.y.rem_euclid(AREA_HEIGHT),
+            }
+        })
+        .collect_vec()
+}
+
+fn quadrant_counts(positions: &[Vec2<i64>]) -> [usize; 4] {
     let mut top_left = 0;
     let mut top_right = 0;
     let mut bottom_left = 0;
@@ -34,20 +127,8 @@ pub fn part_1() -> SolutionResult {
     let area_width_mid_right = AREA_WIDTH / 2 + AREA_WIDTH % 2 - 1;
     let area_height_mid_top = AREA_HEIGHT / 2;
     let area_height_mid_botton = AREA_HEIGHT / 2 + AREA_HEIGHT % 2 - 1;
-    let robots = read_input(file);
 
-    for Robot { position, velocity } in robots {
-        let mut position = position + velocity * ELAPSED;
-        position = Vec2 {
-            x: position.x % AREA_WIDTH,
-            y: position.y % AREA_HEIGHT,
-        };
-        if position.x < 0 {
-            position.x += AREA_WIDTH;
-        }
-        if position.y < 0 {
-            position.y += AREA_HEIGHT;
-        }
+    for position in positions {
         let left = position.x < area_width_mid_left;
         let right = position.x > area_width_mid_right;
         let top = position.y < area_height_mid_top;
@@ -62,59 +143,32 @@ pub fn part_1() -> SolutionResult {
             bottom_right += 1;
         }
     }
-    let safety = top_left * top_right * bottom_left * bottom_right;
-
-    Ok(safety.to_string())
+    [top_left, top_right, bottom_left, bottom_right]
 }
 
-pub fn part_2() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let robots = read_input(file);
+// n^2 times the variance, which keeps the statistic an exact integer
+fn variance_scaled(values: impl Iterator<Item = i64>) -> i64 {
+    let (count, sum, sum_squares) = values.fold((0, 0, 0), |(count, sum, sum_squares), value| {
+        (count + 1, sum + value, sum_squares + value * value)
+    });
+    count * sum_squares - sum * sum
+}
 
-    for elapsed in 0..10000 {
-        let mut positions = robots
-            .iter()
-            .cloned()
-            .map(|Robot { position, velocity }| {
-                let mut new_position = position + velocity * elapsed;
-                new_position.x %= AREA_WIDTH;
-                new_position.y %= AREA_HEIGHT;
-                if new_position.x < 0 {
-                    new_position.x += AREA_WIDTH;
-                }
-                if new_position.y < 0 {
-                    new_position.y += AREA_HEIGHT;
-                }
-                new_position
-            })
-            .collect_vec();
-        positions.sort_by(|pos_1, pos_2| match pos_2.y.cmp(&pos_1.y) {
-            Ordering::Equal => pos_2.x.cmp(&pos_1.x),
-            ord => ord,
-        });
-        // display_positions(&positions);
-
-        let in_target_width = positions
-            .iter()
-            .filter(|pos| {
-                (AREA_WIDTH / 4..AREA_WIDTH * 3 / 4).contains(&pos.x)
-            })
-            .count();
-
-        let in_target_height = positions
-            .iter()
-            .filter(|pos| (AREA_HEIGHT / 4..AREA_HEIGHT * 3 / 4).contains(&pos.y))
-            .count();
-
-        let target = robots.len() - robots.len() / 5;
-
-        if in_target_width >= target && in_target_height >= target {
-            // println!("robots {} target {}", robots.len(), in_target_width);
-            // display_positions(&positions);
-            return Ok(elapsed.to_string());
-        }
+fn longest_run(mut positions: Vec<Vec2<i64>>) -> usize {
+    positions.sort_unstable_by_key(|pos| (pos.y, pos.x));
+    positions.dedup();
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev: Option<Vec2<i64>> = None;
+    for pos in positions {
+        run = match prev {
+            Some(prev) if prev.y == pos.y && prev.x + 1 == pos.x => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        prev = Some(pos);
     }
-    Err("Failed to solve".into())
+    longest
 }
 
 fn read_input(file: File) -> Vec<Robot> {
@@ -137,30 +191,14 @@ fn read_input(file: File) -> Vec<Robot> {
         .collect()
 }
 
-// fn display_positions(positions: &[Vec2<i64>]) {
-//     println!();
-//     let mut pos_idx = Some(positions.len() - 1);
-//     for y in 0..AREA_HEIGHT {
-//         println!();
-//         for x in 0..AREA_WIDTH {
-//             let mut occ = 0;
-//             while let Some(pos) = pos_idx.map(|i| positions[i]) {
-//                 if pos.x == x && pos.y == y {
-//                     pos_idx = pos_idx.and_then(|i| i.checked_sub(1));
-//                     occ += 1;
-//                 } else {
-//                     break;
-//                 }
-//             }
-//             print!(
-//                 "{}",
-//                 if occ == 0 {
-//                     ".".to_string()
-//                 } else {
-//                     occ.to_string()
-//                 }
-//             );
-//         }
-//     }
-//     println!();
-// }
+fn render_positions(positions: &[Vec2<i64>]) -> Frame {
+    Frame::from_points(
+        Vec2 {
+            x: AREA_WIDTH as usize,
+            y: AREA_HEIGHT as usize,
+        },
+        positions.iter().copied(),
+        Glyph::new(ROBOT, Rgb::GREEN),
+        Glyph::new(EMPTY, Rgb::DARK_GREY),
+    )
+}
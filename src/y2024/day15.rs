@@ -1,5 +1,11 @@
-use crate::{get_text_file, math::Vec2, SolutionResult};
+use crate::{
+    get_text_file,
+    math::{checked_product, checked_sum, to_answer, try_checked_sum, Answer, Vec2},
+    visualize::{Frame, Glyph, Recorder, Rgb},
+    AnyError, SolutionResult,
+};
 use ndarray::prelude::*;
+use rustc_hash::FxHashSet;
 use std::{
     fs::File,
     io::{BufRead, BufReader},
@@ -10,6 +16,9 @@ const INPUT_URL: &str = "https://adventofcode.com/2024/day/15/input";
 const EMPTY: char = '.';
 const WALL: char = '#';
 const BOX: char = 'O';
+const BOX_LEFT: char = '[';
+const BOX_MIDDLE: char = '=';
+const BOX_RIGHT: char = ']';
 const ROBOT: char = '@';
 const UP: char = '^';
 const RIGHT: char = '>';
@@ -18,6 +27,9 @@ const LEFT: char = '<';
 
 const GPS_FACTOR: usize = 100;
 
+const SINGLE: Vec2<usize> = Vec2 { x: 1, y: 1 };
+const WIDE: Vec2<usize> = Vec2 { x: 2, y: 1 };
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 enum Direction {
     Up,
@@ -47,7 +59,8 @@ impl Direction {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+// Puzzle input cells
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum Cell {
     Empty,
     Wall,
@@ -64,167 +77,330 @@ impl Cell {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
-enum BoxCell {
-    Left,
-    Right,
+// Warehouse cells, each input cell covers a box sized block of them
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Tile {
+    Empty,
+    Wall,
+    // Index into the box origins
+    Box(usize),
 }
-impl BoxCell {
-    fn indices(&self, index: Vec2<usize>) -> (Vec2<usize>, Vec2<usize>) {
-        match self {
-            BoxCell::Left => (
-                index,
-                Vec2 {
-                    x: index.x + 1,
-                    ..index
-                },
-            ),
-            BoxCell::Right => (
-                Vec2 {
-                    x: index.x - 1,
-                    ..index
-                },
-                index,
-            ),
-        }
-    }
+
+// An applied move, undone by pushing the same boxes back
+#[derive(Clone, Debug)]
+struct Move {
+    robot: Vec2<usize>,
+    pushed: Vec<usize>,
+    blocked: bool,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
-enum WideCell {
-    Empty,
-    Wall,
-    Box(BoxCell),
+#[derive(Clone, Debug)]
+struct Warehouse {
+    tiles: Array2<Tile>,
+    // Top left tile of each box
+    boxes: Vec<Vec2<usize>>,
+    box_size: Vec2<usize>,
+    robot: Vec2<usize>,
+    directions: Vec<Direction>,
+    // One entry per move made so far
+    history: Vec<Move>,
+    blocked: usize,
+}
+
+#[derive(Debug)]
+struct StateDiff {
+    tiles: Vec<Vec2<usize>>,
+    robot: Option<(Vec2<usize>, Vec2<usize>)>,
 }
-impl WideCell {
-    fn from_cell(cell: Cell) -> [Self; 2] {
-        match cell {
-            Cell::Empty => [Self::Empty, Self::Empty],
-            Cell::Wall => [Self::Wall, Self::Wall],
-            Cell::Box => [Self::Box(BoxCell::Left), Self::Box(BoxCell::Right)],
+
+impl Warehouse {
+    // Scales every input cell up to a block of the box size, the robot stays a single
+    // tile in the top left of its block
+    fn new(
+        cells: &Array2<Cell>,
+        robot: Vec2<usize>,
+        directions: Vec<Direction>,
+        box_size: Vec2<usize>,
+    ) -> Self {
+        let (rows, cols) = cells.dim();
+        let mut tiles = Array2::from_elem((rows * box_size.y, cols * box_size.x), Tile::Empty);
+        let mut boxes = Vec::new();
+        for ((y, x), cell) in cells.indexed_iter() {
+            let origin = Vec2 {
+                x: x * box_size.x,
+                y: y * box_size.y,
+            };
+            let tile = match cell {
+                Cell::Empty => continue,
+                Cell::Wall => Tile::Wall,
+                Cell::Box => {
+                    boxes.push(origin);
+                    Tile::Box(boxes.len() - 1)
+                }
+            };
+            for index in block(origin, box_size) {
+                tiles[index] = tile;
+            }
+        }
+        Self {
+            tiles,
+            boxes,
+            box_size,
+            robot: Vec2 {
+                x: robot.x * box_size.x,
+                y: robot.y * box_size.y,
+            },
+            directions,
+            history: Vec::new(),
+            blocked: 0,
         }
     }
 
-    fn unwrap_box(self) -> BoxCell {
-        match self {
-            Self::Box(b) => b,
-            _ => panic!(),
+    fn moves_made(&self) -> usize {
+        self.history.len()
+    }
+
+    // Makes the next move, None when all moves are made
+    fn step(&mut self) -> Option<&Move> {
+        let unit = self.directions.get(self.history.len())?.unit_vec();
+        let target = self
+            .robot
+            .signed_add(unit)
+            .and_then(|target| Some((target, self.cascade(target, unit)?)));
+        let applied = match target {
+            Some((target, pushed)) => {
+                self.shift(&pushed, unit);
+                let robot = std::mem::replace(&mut self.robot, target);
+                Move {
+                    robot,
+                    pushed,
+                    blocked: false,
+                }
+            }
+            None => {
+                self.blocked += 1;
+                Move {
+                    robot: self.robot,
+                    pushed: Vec::new(),
+                    blocked: true,
+                }
+            }
+        };
+        self.history.push(applied);
+        self.history.last()
+    }
+
+    fn undo(&mut self) -> Option<Move> {
+        let applied = self.history.pop()?;
+        let unit = self.directions[self.history.len()].unit_vec();
+        self.shift(&applied.pushed, -unit);
+        self.robot = applied.robot;
+        if applied.blocked {
+            self.blocked -= 1;
         }
+        Some(applied)
     }
-}
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let (mut cells, directions, mut robot_pos) = read_input(file);
-    // println!("Initial state:");
-    // display_cells(&cells, &robot_pos);
-    // println!("directions\n{:?}", directions);
-    // println!("robot_pos {:?}", robot_pos);
-
-    for direction in directions.into_iter().map(|d| d.unit_vec()) {
-        // println!(
-        //     "\nMove {:?}",
-        //     match direction {
-        //         Vec2 { x: 0, y: -1 } => UP,
-        //         Vec2 { x: 1, y: 0 } => RIGHT,
-        //         Vec2 { x: 0, y: 1 } => DOWN,
-        //         Vec2 { x: -1, y: 0 } => LEFT,
-        //         _ => panic!(),
-        //     }
-        // );
-        if let Some(move_index) = robot_pos.signed_add(direction) {
-            if let Some(move_cell) = cells.get(move_index) {
-                if *move_cell == Cell::Box {
-                    let mut index = move_index.signed_add(direction);
-                    while let Some(cell) = index.and_then(|i| cells.get(i)) {
-                        match &cell {
-                            Cell::Empty => {
-                                cells[move_index] = Cell::Empty;
-                                cells[index.unwrap()] = Cell::Box;
-                                break;
-                            }
-                            Cell::Wall => break,
-                            Cell::Box => index = index.unwrap().signed_add(direction),
+    // Steps or undoes until the given number of moves is made
+    fn replay_to(&mut self, moves: usize) -> Result<(), AnyError> {
+        if moves > self.directions.len() {
+            return Err(format!("Only {} moves to replay", self.directions.len()).into());
+        }
+        while self.moves_made() > moves {
+            self.undo();
+        }
+        while self.moves_made() < moves {
+            self.step();
+        }
+        Ok(())
+    }
+
+    fn run(&mut self) {
+        while self.step().is_some() {}
+    }
+
+    // Boxes pushed when something enters the target tile, None when a wall is in the
+    // way. Every box pushes the boxes in front of any of its tiles.
+    fn cascade(&self, target: Vec2<usize>, unit: Vec2<isize>) -> Option<Vec<usize>> {
+        let mut pushed = Vec::new();
+        let mut seen = FxHashSet::default();
+        let mut ahead = vec![target];
+        let mut next = 0;
+        loop {
+            for index in ahead.drain(..) {
+                match self.tiles.get(index)? {
+                    Tile::Empty => {}
+                    Tile::Wall => return None,
+                    Tile::Box(id) => {
+                        if seen.insert(*id) {
+                            pushed.push(*id);
                         }
                     }
                 }
-                if cells[move_index] == Cell::Empty {
-                    robot_pos = move_index;
+            }
+            let Some(id) = pushed.get(next) else {
+                return Some(pushed);
+            };
+            for index in block(self.boxes[*id], self.box_size) {
+                let index = index.signed_add(unit)?;
+                if self.tiles.get(index) != Some(&Tile::Box(*id)) {
+                    ahead.push(index);
                 }
             }
+            next += 1;
         }
-        // display_cells(&cells, &robot_pos);
     }
 
-    let gps: usize = cells
-        .indexed_iter()
-        .filter_map(|((y, x), cell)| match cell {
-            Cell::Box => Some(GPS_FACTOR * y + x),
-            _ => None,
-        })
-        .sum();
+    fn shift(&mut self, pushed: &[usize], unit: Vec2<isize>) {
+        // Clear every box before placing them, as boxes move into each other's tiles
+        for id in pushed {
+            for index in block(self.boxes[*id], self.box_size) {
+                self.tiles[index] = Tile::Empty;
+            }
+        }
+        for id in pushed {
+            self.boxes[*id] = self.boxes[*id].signed_add(unit).unwrap();
+            for index in block(self.boxes[*id], self.box_size) {
+                self.tiles[index] = Tile::Box(*id);
+            }
+        }
+    }
 
-    Ok(gps.to_string())
+    fn diff(&self, other: &Self) -> StateDiff {
+        StateDiff {
+            tiles: self
+                .tiles
+                .indexed_iter()
+                .filter(|(index, tile)| other.tiles.get(*index) != Some(*tile))
+                .map(|(index, _)| Vec2::from_index_tuple(index))
+                .collect(),
+            robot: (self.robot != other.robot).then_some((self.robot, other.robot)),
+        }
+    }
+
+    fn gps(&self) -> Result<Answer, AnyError> {
+        try_checked_sum(
+            self.boxes
+                .iter()
+                .map(|origin| gps_coordinate(origin.y, origin.x)),
+        )
+    }
+
+    fn render(&self) -> Frame {
+        let mut frame = Frame::from_grid(&self.tiles, |tile| match tile {
+            Tile::Wall => Glyph::new(WALL, Rgb::GREY),
+            Tile::Empty | Tile::Box(_) => Glyph::new(EMPTY, Rgb::DARK_GREY),
+        });
+        for origin in &self.boxes {
+            for index in block(*origin, self.box_size) {
+                let symbol = match (index.x - origin.x, self.box_size.x) {
+                    (_, 1) => BOX,
+                    (0, _) => BOX_LEFT,
+                    (x, width) if x + 1 == width => BOX_RIGHT,
+                    _ => BOX_MIDDLE,
+                };
+                frame.set(index, Glyph::new(symbol, Rgb::BROWN));
+            }
+        }
+        frame.set(self.robot, Glyph::new(ROBOT, Rgb::RED));
+        frame
+    }
+}
+
+impl StateDiff {
+    fn is_empty(&self) -> bool {
+        self.tiles.is_empty() && self.robot.is_none()
+    }
+}
+
+pub fn part_1() -> SolutionResult {
+    scaled_gps(SINGLE.x, SINGLE.y)
 }
 
 pub fn part_2() -> SolutionResult {
+    scaled_gps(WIDE.x, WIDE.y)
+}
+
+// GPS sum with boxes, walls and free space scaled up to the given size
+pub fn scaled_gps(width: usize, height: usize) -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
     let (cells, directions, robot_pos) = read_input(file);
-    let (rows, cols) = cells.dim();
-    let mut cells = Array2::from_shape_vec(
-        (rows, cols * 2),
-        cells
-            .as_standard_layout()
-            .into_iter()
-            .flat_map(|cell| WideCell::from_cell(cell))
-            .collect(),
-    )
-    .unwrap();
-    let mut robot_pos = Vec2 {
-        x: robot_pos.x * 2,
-        ..robot_pos
+
+    let mut warehouse = Warehouse::new(&cells, robot_pos, directions, box_size(width, height)?);
+    warehouse.run();
+
+    Ok(warehouse.gps()?.to_string())
+}
+
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let (cells, directions, robot_pos) = read_input(file);
+
+    let box_size = match part {
+        1 => SINGLE,
+        2 => WIDE,
+        _ => return Err("Part does not exist".into()),
     };
+    // Runs all moves, then undoes them again to check the history restores the start
+    let start = Warehouse::new(&cells, robot_pos, directions, box_size);
+    let mut warehouse = start.clone();
+    warehouse.run();
+    let moved = start.diff(&warehouse);
+    let explanation = format!(
+        "{} moves, {} blocked, {} tiles changed, GPS sum {}",
+        warehouse.moves_made(),
+        warehouse.blocked,
+        moved.tiles.len(),
+        warehouse.gps()?
+    );
 
-    // println!("Initial state");
-    // display_wide_cells(&cells, &robot_pos);
-    for direction in directions {
-        // println!(
-        //     "\nMove {:?}",
-        //     match direction {
-        //         Direction::Up => UP,
-        //         Direction::Right => RIGHT,
-        //         Direction::Down => DOWN,
-        //         Direction::Left => LEFT,
-        //     }.to_string()
-        // );
-        let unit_direction = direction.unit_vec();
-        if let Some(move_index) = robot_pos.signed_add(unit_direction) {
-            if match cells.get(move_index) {
-                Some(WideCell::Empty) => true,
-                Some(WideCell::Wall) | None => false,
-                Some(WideCell::Box(_)) => match direction {
-                    Direction::Up | Direction::Down => {
-                        move_boxes_vertical(&mut cells, move_index, unit_direction)
-                    }
-                    Direction::Right | Direction::Left => {
-                        move_boxes_horizontal(&mut cells, move_index, unit_direction)
-                    }
-                },
-            } {
-                robot_pos = move_index;
-            }
-        }
-        // display_wide_cells(&cells, &robot_pos);
+    warehouse.replay_to(0)?;
+    let restored = start.diff(&warehouse);
+    if !restored.is_empty() {
+        return Err(format!(
+            "Undoing all moves leaves {} tiles changed, robot {:?}",
+            restored.tiles.len(),
+            restored.robot
+        )
+        .into());
     }
-    let gps: usize = cells
-        .indexed_iter()
-        .filter_map(|((y, x), cell)| match cell {
-            WideCell::Box(BoxCell::Left) => Some(GPS_FACTOR * y + x),
-            _ => None,
-        })
-        .sum();
+    Ok(explanation)
+}
+
+pub fn visualize(recorder: &mut Recorder) -> Result<(), AnyError> {
+    let file = get_text_file(INPUT_URL)?;
+    let (cells, directions, robot_pos) = read_input(file);
+    let mut warehouse = Warehouse::new(&cells, robot_pos, directions, WIDE);
+
+    recorder.record(warehouse.render());
+    while warehouse.step().is_some() {
+        recorder.record_step(|| warehouse.render());
+    }
+    recorder.record(warehouse.render());
+    Ok(())
+}
+
+fn box_size(width: usize, height: usize) -> Result<Vec2<usize>, AnyError> {
+    if width == 0 || height == 0 {
+        return Err("Boxes need a size of at least 1x1".into());
+    }
+    Ok(Vec2 {
+        x: width,
+        y: height,
+    })
+}
 
-    Ok(gps.to_string())
+// Tiles of the block with the origin as its top left corner
+fn block(origin: Vec2<usize>, size: Vec2<usize>) -> impl Iterator<Item = Vec2<usize>> {
+    (origin.y..origin.y + size.y)
+        .flat_map(move |y| (origin.x..origin.x + size.x).map(move |x| Vec2 { x, y }))
+}
+
+fn gps_coordinate(y: usize, x: usize) -> Result<Answer, AnyError> {
+    checked_sum([
+        checked_product([to_answer(GPS_FACTOR)?, to_answer(y)?])?,
+        to_answer(x)?,
+    ])
 }
 
 fn read_input(file: File) -> (Array2<Cell>, Vec<Direction>, Vec2<usize>) {
@@ -260,117 +436,3 @@ fn read_input(file: File) -> (Array2<Cell>, Vec<Direction>, Vec2<usize>) {
         robot_pos.unwrap(),
     )
 }
-
-fn move_boxes_horizontal(
-    cells: &mut Array2<WideCell>,
-    move_index: Vec2<usize>,
-    unit_direction: Vec2<isize>,
-) -> bool {
-    let mut box_indicies = vec![move_index];
-    let mut index = 0;
-    while let Some(box_idx) = box_indicies.get(index) {
-        let next_box_idx_opt = box_idx.signed_add(unit_direction);
-        match next_box_idx_opt.and_then(|i| cells.get(i)) {
-            Some(WideCell::Empty) => break,
-            Some(WideCell::Wall) | None => return false,
-            Some(WideCell::Box(_)) => box_indicies.push(next_box_idx_opt.unwrap()),
-        }
-        index += 1;
-    }
-    for box_idx in box_indicies.iter().rev() {
-        cells[box_idx.signed_add(unit_direction).unwrap()] = cells[*box_idx].clone();
-    }
-    cells[*box_indicies.first().unwrap()] = WideCell::Empty;
-    true
-}
-
-fn move_boxes_vertical(
-    cells: &mut Array2<WideCell>,
-    move_index: Vec2<usize>,
-    unit_direction: Vec2<isize>,
-) -> bool {
-    let move_box_cell = cells[move_index].clone().unwrap_box();
-    let mut boxes = vec![move_box_cell.indices(move_index)];
-    let mut next_box_idx = 0;
-
-    let push_box = |box_side_idx: Vec2<usize>| {
-        if let Some(next_index) = box_side_idx.signed_add(unit_direction) {
-            match cells.get(next_index) {
-                Some(WideCell::Wall) | None => None,
-                Some(WideCell::Empty) => Some(None),
-                Some(WideCell::Box(box_cell)) => Some(Some(box_cell.indices(next_index))),
-            }
-        } else {
-            None
-        }
-    };
-
-    while let Some((left_side, right_side)) = boxes.get(next_box_idx) {
-        if let (Some(next_left_box_opt), Some(next_right_box_opt)) =
-            (push_box(*left_side), push_box(*right_side))
-        {
-            if let Some(next_left_box) = next_left_box_opt {
-                boxes.push(next_left_box);
-            }
-            if let Some(next_right_box) = next_right_box_opt {
-                if boxes.last().map_or(true, |b| *b != next_right_box) {
-                    boxes.push(next_right_box);
-                }
-            }
-        } else {
-            return false;
-        }
-        next_box_idx += 1;
-    }
-
-    for (left_side, right_side) in boxes.iter().rev() {
-        cells[*left_side] = WideCell::Empty;
-        cells[*right_side] = WideCell::Empty;
-        cells[left_side.signed_add(unit_direction).unwrap()] = WideCell::Box(BoxCell::Left);
-        cells[right_side.signed_add(unit_direction).unwrap()] = WideCell::Box(BoxCell::Right);
-    }
-    true
-}
-
-// fn display_cells(cells: &Array2<Cell>, robot_pos: &Vec2<usize>) {
-//     for (y, row) in cells.axis_iter(Axis(0)).enumerate() {
-//         for (x, cell) in row.iter().enumerate() {
-//             print!(
-//                 "{}",
-//                 if *robot_pos == (Vec2 { x, y }) {
-//                     ROBOT
-//                 } else {
-//                     match cell {
-//                         Cell::Empty => EMPTY,
-//                         Cell::Wall => WALL,
-//                         Cell::Box => BOX,
-//                     }
-//                 }
-//                 .to_string()
-//             )
-//         }
-//         println!();
-//     }
-// }
-
-// fn display_wide_cells(cells: &Array2<WideCell>, robot_pos: &Vec2<usize>) {
-//     for (y, row) in cells.axis_iter(Axis(0)).enumerate() {
-//         for (x, cell) in row.iter().enumerate() {
-//             print!(
-//                 "{}",
-//                 if *robot_pos == (Vec2 { x, y }) {
-//                     ROBOT
-//                 } else {
-//                     match cell {
-//                         WideCell::Empty => EMPTY,
-//                         WideCell::Wall => WALL,
-//                         WideCell::Box(BoxCell::Left) => '[',
-//                         WideCell::Box(BoxCell::Right) => ']',
-//                     }
-//                 }
-//                 .to_string()
-//             )
-//         }
-//         println!();
-//     }
-// }
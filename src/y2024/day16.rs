@@ -1,11 +1,19 @@
-use crate::{get_text_file, math::Vec2, utils::FxDiGraphMap, SolutionResult};
+use crate::{
+    get_text_file,
+    math::Vec2,
+    utils::FxDiGraphMap,
+    visualize::{Frame, Glyph, Recorder, Rgb},
+    AnyError, SolutionResult,
+};
+use itertools::Itertools;
 use ndarray::prelude::*;
 use petgraph::{
     algo::{astar, dijkstra},
     prelude::*,
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
+    fmt::Write,
     fs::File,
     io::{BufRead, BufReader},
 };
@@ -16,11 +24,25 @@ const EMPTY: char = '.';
 const WALL: char = '#';
 const START: char = 'S';
 const END: char = 'E';
+const PATH: char = 'O';
+const UP: char = '^';
+const RIGHT: char = '>';
+const DOWN: char = 'v';
+const LEFT: char = '<';
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
 
-const STEP_COST: u64 = 1;
-const NO_TURN_COST: u64 = 0;
-const TURN_COST: u64 = 1000;
-const INITIAL_DIRECTION: Direction = Direction::Right;
+const REINDEER_COSTS: CostModel = CostModel {
+    step: 1,
+    turn: 1000,
+    u_turn: None,
+    start_directions: &[Direction::Right],
+};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Copy, PartialOrd, Ord)]
 enum Direction {
@@ -50,12 +72,12 @@ impl Direction {
         }
     }
 
-    fn rotate_90(self) -> Self {
+    fn symbol(&self) -> char {
         match self {
-            Self::Up => Self::Right,
-            Self::Right => Self::Down,
-            Self::Down => Self::Left,
-            Self::Left => Self::Up,
+            Self::Up => UP,
+            Self::Right => RIGHT,
+            Self::Down => DOWN,
+            Self::Left => LEFT,
         }
     }
 
@@ -77,21 +99,149 @@ enum Cell {
 
 type Intersection = (Vec2<usize>, Direction);
 
+#[derive(Clone, Debug)]
+struct CostModel {
+    step: u64,
+    // Cost of a 90 degree turn
+    turn: u64,
+    // Cost of turning around on the spot, None forbids it
+    u_turn: Option<u64>,
+    start_directions: &'static [Direction],
+}
+
+impl CostModel {
+    fn turn_cost(&self, from: Direction, to: Direction) -> Option<u64> {
+        if to == from {
+            Some(0)
+        } else if to == from.flip() {
+            self.u_turn
+        } else {
+            Some(self.turn)
+        }
+    }
+
+    // Least cost of the turns needed to face every direction the target lies in
+    fn min_turns_cost(&self, direction: Direction, required: &[Direction]) -> u64 {
+        let turns = required.len().saturating_sub(1) as u64;
+        if required.is_empty() || required.contains(&direction) {
+            turns * self.turn
+        } else if required.contains(&direction.flip()) {
+            let around = 2 * self.turn;
+            self.u_turn
+                .map_or(around, |u_turn| around.min(u_turn + turns * self.turn))
+        } else {
+            self.turn
+        }
+    }
+}
+
+// Graph of the (tile, direction) states at intersections, with edges which turn and
+// then walk straight to the next intersection
+struct Maze {
+    graph: FxDiGraphMap<Intersection, u64>,
+    start: Vec2<usize>,
+    end: Vec2<usize>,
+    costs: CostModel,
+}
+
+impl Maze {
+    fn new(cells: &Array2<Cell>, start: Vec2<usize>, end: Vec2<usize>, costs: CostModel) -> Self {
+        let mut graph: FxDiGraphMap<Intersection, u64> = GraphMap::new();
+        // Directions to leave each intersection in, with the tiles to the next one
+        let mut corridors: FxHashMap<Vec2<usize>, Vec<(Direction, u64)>> = FxHashMap::default();
+
+        let mut nodes = costs
+            .start_directions
+            .iter()
+            .map(|direction| graph.add_node((start, *direction)))
+            .collect_vec();
+        while let Some(node) = nodes.pop() {
+            let (index, direction) = node;
+            let exits = corridors
+                .entry(index)
+                .or_insert_with(|| find_corridors(cells, index, end));
+            for (next_direction, tiles) in exits.iter() {
+                let Some(turn_cost) = costs.turn_cost(direction, *next_direction) else {
+                    continue;
+                };
+                let next_index = index
+                    .signed_add(next_direction.to_unit_vec() * *tiles as isize)
+                    .unwrap();
+                let next_node = (next_index, *next_direction);
+                if !graph.contains_node(next_node) {
+                    nodes.push(next_node);
+                }
+                graph.add_edge(node, next_node, turn_cost + tiles * costs.step);
+            }
+        }
+        Self {
+            graph,
+            start,
+            end,
+            costs,
+        }
+    }
+
+    fn start_nodes(&self) -> impl Iterator<Item = Intersection> + '_ {
+        self.costs
+            .start_directions
+            .iter()
+            .map(|direction| (self.start, *direction))
+    }
+
+    // Lower bound of the score from the node to the end, ignoring walls
+    fn heuristic(&self, (index, direction): Intersection) -> u64 {
+        let (from, to) = (
+            index.convert::<isize>().unwrap(),
+            self.end.convert::<isize>().unwrap(),
+        );
+        let required = [
+            (to.x > from.x, Direction::Right),
+            (to.x < from.x, Direction::Left),
+            (to.y > from.y, Direction::Down),
+            (to.y < from.y, Direction::Up),
+        ]
+        .into_iter()
+        .filter_map(|(needed, direction)| needed.then_some(direction))
+        .collect_vec();
+        let distance = (to - from).abs();
+        (distance.x + distance.y) as u64 * self.costs.step
+            + self.costs.min_turns_cost(direction, &required)
+    }
+
+    fn shortest_path(&self) -> Option<(u64, Vec<Intersection>)> {
+        self.start_nodes()
+            .filter_map(|start_node| {
+                astar(
+                    &self.graph,
+                    start_node,
+                    |(index, _)| index == self.end,
+                    |e| *e.weight(),
+                    |node| self.heuristic(node),
+                )
+            })
+            .min_by_key(|(score, _)| *score)
+    }
+
+    // Lowest score of every node from any start
+    fn node_scores(&self) -> FxHashMap<Intersection, u64> {
+        let mut node_scores: FxHashMap<Intersection, u64> = FxHashMap::default();
+        for start_node in self.start_nodes() {
+            for (node, score) in dijkstra(&self.graph, start_node, None, |e| *e.weight()) {
+                let best = node_scores.entry(node).or_insert(score);
+                *best = (*best).min(score);
+            }
+        }
+        node_scores
+    }
+}
+
 pub fn part_1() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
     let (cells, start, end) = read_input(file);
 
-    let maze: FxDiGraphMap<Intersection, u64> = create_maze(&cells, start, end, INITIAL_DIRECTION);
-    let start_node = maze.nodes().find(|(idx, _)| *idx == start).unwrap();
-    let score = astar(
-        &maze,
-        start_node,
-        |(idx, _)| idx == end,
-        |e| *e.weight(),
-        |_| 0,
-    )
-    .unwrap()
-    .0;
+    let maze = Maze::new(&cells, start, end, REINDEER_COSTS);
+    let (score, _) = maze.shortest_path().ok_or("No path to the end")?;
 
     Ok(score.to_string())
 }
@@ -99,61 +249,156 @@ pub fn part_1() -> SolutionResult {
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
     let (cells, start, end) = read_input(file);
-    let maze: FxDiGraphMap<Intersection, u64> = create_maze(&cells, start, end, INITIAL_DIRECTION);
-
-    let start_node = maze.nodes().find(|(idx, _)| *idx == start).unwrap();
-    let node_scores = dijkstra(&maze, start_node, None, |e| *e.weight());
-    let end_node = node_scores
-        .iter()
-        .filter(|((idx, _), _)| *idx == end)
-        .min_by_key(|(_, score)| *score)
-        .unwrap();
-
-    let mut path: FxHashMap<Vec2<usize>, FxHashMap<Vec2<usize>, u64>> = FxHashMap::default();
-    let mut check_path = vec![(*end_node.0, *end_node.1)];
-    while let Some((node, score)) = check_path.pop() {
-        let (index, direction) = node;
-        check_path.extend(maze.edges_directed(node, Incoming).filter_map(
-            |(neighbor, n2, weight)| {
-                assert!(neighbor != node);
-                assert!(n2 == node);
-                let neighbor_score = node_scores[&neighbor];
-                if weight + neighbor_score == score {
-                    let (neighbor_index, neighbor_direction) = neighbor;
-                    let tiles = if neighbor_direction != direction {
-                        weight - TURN_COST
-                    } else {
-                        *weight
-                    };
-                    path.entry(neighbor_index)
-                        .or_insert(FxHashMap::default())
-                        .insert(index, tiles);
-                    Some((neighbor, neighbor_score))
-                } else {
-                    None
+
+    let maze = Maze::new(&cells, start, end, REINDEER_COSTS);
+    let optimal_paths = OptimalPaths::new(&maze)?;
+
+    Ok(optimal_paths.tiles.len().to_string())
+}
+
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let (cells, start, end) = read_input(file);
+
+    let maze = Maze::new(&cells, start, end, REINDEER_COSTS);
+    let optimal_paths = OptimalPaths::new(&maze)?;
+    let mut explanation = String::new();
+    writeln!(
+        explanation,
+        "Score {} facing {}",
+        optimal_paths.score,
+        optimal_paths
+            .ends
+            .iter()
+            .map(Direction::symbol)
+            .join(" or ")
+    )?;
+    match part {
+        1 => {}
+        2 => writeln!(
+            explanation,
+            "{} tiles on the best paths\n{}",
+            optimal_paths.tiles.len(),
+            optimal_paths.render(&cells, start, end).to_plain_string()
+        )?,
+        _ => return Err("Part does not exist".into()),
+    }
+    Ok(explanation)
+}
+
+pub fn visualize(recorder: &mut Recorder) -> Result<(), AnyError> {
+    let file = get_text_file(INPUT_URL)?;
+    let (cells, start, end) = read_input(file);
+
+    let maze = Maze::new(&cells, start, end, REINDEER_COSTS);
+    let (_, path) = maze.shortest_path().ok_or("No path to the end")?;
+
+    let mut frame = render_cells(&cells, start, end);
+    recorder.record(frame.clone());
+    for ((from, _), (to, direction)) in path.into_iter().tuple_windows() {
+        let mut index = from;
+        while index != to {
+            index = index.signed_add(direction.to_unit_vec()).unwrap();
+            frame.set(index, Glyph::new(PATH, Rgb::YELLOW));
+            recorder.record_step(|| frame.clone());
+        }
+    }
+    frame.set(end, Glyph::new(END, Rgb::RED));
+    recorder.record(frame);
+    Ok(())
+}
+
+// Every lowest score path from the start to the end, in any end orientation
+struct OptimalPaths {
+    score: u64,
+    ends: Vec<Direction>,
+    // Directions faced on each tile of the paths
+    tiles: FxHashMap<Vec2<usize>, FxHashSet<Direction>>,
+}
+
+impl OptimalPaths {
+    fn new(maze: &Maze) -> Result<Self, AnyError> {
+        let (start, end) = (maze.start, maze.end);
+        let node_scores = maze.node_scores();
+        let score = *node_scores
+            .iter()
+            .filter(|((index, _), _)| *index == end)
+            .map(|(_, score)| score)
+            .min()
+            .ok_or("No path to the end")?;
+        let ends = node_scores
+            .iter()
+            .filter(|((index, _), node_score)| *index == end && **node_score == score)
+            .map(|((_, direction), _)| *direction)
+            .sorted()
+            .collect_vec();
+
+        // Walks back over the edges which continue a lowest score path
+        let mut tiles: FxHashMap<Vec2<usize>, FxHashSet<Direction>> = FxHashMap::default();
+        let mut nodes = ends.iter().map(|direction| (end, *direction)).collect_vec();
+        let mut visited: FxHashSet<Intersection> = nodes.iter().copied().collect();
+        while let Some(node) = nodes.pop() {
+            let (index, direction) = node;
+            for (previous, _, weight) in maze.graph.edges_directed(node, Incoming) {
+                if node_scores.get(&previous).map(|score| score + weight)
+                    != Some(node_scores[&node])
+                {
+                    continue;
                 }
-            },
-        ));
+                // Turns on the previous tile, then walks straight
+                let mut tile = previous.0;
+                loop {
+                    tiles.entry(tile).or_default().insert(direction);
+                    if tile == index {
+                        break;
+                    }
+                    tile = tile.signed_add(direction.to_unit_vec()).unwrap();
+                }
+                if visited.insert(previous) {
+                    nodes.push(previous);
+                }
+            }
+        }
+        // Facing the start directions which begin a path
+        for (_, direction) in maze
+            .start_nodes()
+            .filter(|node| visited.contains(node) && node_scores.get(node) == Some(&0))
+        {
+            tiles.entry(start).or_default().insert(direction);
+        }
+        Ok(Self { score, ends, tiles })
     }
-    let tiles = path
-        .values()
-        .map(|sources| sources.values().sum::<u64>() - sources.len() as u64 + 1)
-        .sum::<u64>()
-        + 1;
 
-    Ok(tiles.to_string())
+    // Tiles faced in a single direction show it, others show a plain path
+    fn render(&self, cells: &Array2<Cell>, start: Vec2<usize>, end: Vec2<usize>) -> Frame {
+        let mut frame = render_cells(cells, start, end);
+        for (tile, directions) in &self.tiles {
+            if *tile == start || *tile == end {
+                continue;
+            }
+            let symbol = match directions.iter().exactly_one() {
+                Ok(direction) => direction.symbol(),
+                Err(_) => PATH,
+            };
+            frame.set(*tile, Glyph::new(symbol, Rgb::YELLOW));
+        }
+        frame
+    }
 }
 
 fn read_input(file: File) -> (Array2<Cell>, Vec2<usize>, Vec2<usize>) {
+    parse_maze(BufReader::new(file).lines().map(Result::unwrap))
+}
+
+fn parse_maze(lines: impl Iterator<Item = String>) -> (Array2<Cell>, Vec2<usize>, Vec2<usize>) {
     let mut cells = Vec::new();
     let mut start = None;
     let mut end = None;
 
-    let rows = BufReader::new(file)
-        .lines()
+    let rows = lines
         .enumerate()
         .map(|(row, line)| {
-            cells.extend(line.unwrap().chars().enumerate().filter_map(|(col, ch)| {
+            cells.extend(line.chars().enumerate().filter_map(|(col, ch)| {
                 Some(match ch {
                     EMPTY => Cell::Empty,
                     WALL => Cell::Wall,
@@ -179,145 +424,160 @@ fn read_input(file: File) -> (Array2<Cell>, Vec2<usize>, Vec2<usize>) {
     )
 }
 
-fn create_maze(
+// Tiles to walk from the index to the next intersection or the end, per direction
+fn find_corridors(
     cells: &Array2<Cell>,
-    start: Vec2<usize>,
+    index: Vec2<usize>,
     end: Vec2<usize>,
-    initial_direction: Direction,
-) -> FxDiGraphMap<Intersection, u64> {
-    let mut maze: FxDiGraphMap<Intersection, u64> = GraphMap::new();
-    let mut visited: FxHashMap<Vec2<usize>, Vec<(Direction, u64)>> = FxHashMap::default();
-
+) -> Vec<(Direction, u64)> {
     let get_empty_cell = |index: Vec2<usize>, offset: Vec2<isize>| {
         index
             .signed_add(offset)
             .filter(|i| matches!(cells.get(*i), Some(Cell::Empty)))
     };
 
-    let mut nodes = vec![(start, initial_direction)];
-    while let Some(node) = nodes.pop() {
-        let (index, direction) = node;
-
-        if let Some(paths) = visited.get(&index) {
-            for (next_direction, weight) in paths {
-                let next_index = index
-                    .signed_add(next_direction.to_unit_vec() * *weight as isize)
-                    .unwrap();
-                let weight: u64 = weight
-                    + if *next_direction == direction {
-                        NO_TURN_COST
-                    } else if *next_direction == direction.rotate_90()
-                        || *next_direction == direction.rotate_90().flip()
-                    {
-                        TURN_COST
-                    } else {
-                        continue;
-                    };
-                let next_node = (next_index, *next_direction);
-                if !maze.contains_node(next_node) {
-                    nodes.push(next_node);
-                }
-                maze.add_edge(node, next_node, weight);
+    let mut corridors = Vec::new();
+    for direction in DIRECTIONS {
+        let step = direction.to_unit_vec();
+        let mut next_index_opt = get_empty_cell(index, step);
+        let mut tiles = 0;
+        while let Some(next_index) = next_index_opt {
+            tiles += 1;
+            let side_1 = get_empty_cell(
+                next_index,
+                Vec2 {
+                    x: -step.y,
+                    y: step.x,
+                },
+            );
+            let side_2 = get_empty_cell(
+                next_index,
+                Vec2 {
+                    x: step.y,
+                    y: -step.x,
+                },
+            );
+            if side_1.is_some() || side_2.is_some() || next_index == end {
+                corridors.push((direction, tiles));
+                break;
             }
-        } else {
-            let mut edge_weights = Vec::new();
-
-            for (next_direction, turn_weight) in [
-                (direction, Some(NO_TURN_COST)),
-                (direction.rotate_90(), Some(TURN_COST)),
-                (direction.rotate_90().flip(), Some(TURN_COST)),
-                (direction.flip(), None),
-            ] {
-                let step = next_direction.to_unit_vec();
-                let mut next_index_opt = get_empty_cell(index, step);
-                let mut next_weight = 0;
-                while let Some(next_index) = next_index_opt {
-                    next_weight += STEP_COST;
-                    let side_1 = get_empty_cell(
-                        next_index,
-                        Vec2 {
-                            x: -step.y,
-                            y: step.x,
-                        },
-                    );
-                    let side_2 = get_empty_cell(
-                        next_index,
-                        Vec2 {
-                            x: step.y,
-                            y: -step.x,
-                        },
-                    );
-                    if side_1.is_some() || side_2.is_some() || next_index == end {
-                        edge_weights.push((next_direction, next_weight));
-                        if let Some(turn_weight) = turn_weight {
-                            let next_node = (next_index, next_direction);
-                            if !maze.contains_node(next_node) {
-                                nodes.push(next_node);
-                            }
-                            maze.add_edge(node, next_node, next_weight + turn_weight);
-                        }
-                        break;
-                    }
-                    next_index_opt = get_empty_cell(next_index, step);
-                }
-            }
-            visited.insert(index, edge_weights);
+            next_index_opt = get_empty_cell(next_index, step);
         }
     }
-    maze
+    corridors
+}
+
+fn render_cells(cells: &Array2<Cell>, start: Vec2<usize>, end: Vec2<usize>) -> Frame {
+    let mut frame = Frame::from_grid(cells, |cell| match cell {
+        Cell::Empty => Glyph::new(EMPTY, Rgb::DARK_GREY),
+        Cell::Wall => Glyph::new(WALL, Rgb::GREY),
+    });
+    frame.set(start, Glyph::new(START, Rgb::GREEN));
+    frame.set(end, Glyph::new(END, Rgb::RED));
+    frame
 }
 
-// fn display_maze(maze: &FxDiGraphMap<Intersection, u64>) {
-//     for node in maze.nodes() {
-//         let (idx, direction) = node;
-//         println!("<< (x: {}, y: {}), {:?} >>", idx.x, idx.y, direction);
-//         print!("Outgoing: ");
-//         for (n1, neighbor, weight) in maze.edges_directed(node, Outgoing) {
-//             assert!(maze.is_adjacent(&(), n1, neighbor));
-//             assert!(n1 == node);
-//             // let neighbor = n2;
-//             // let neighbor = if n1 == node { n2 } else { n1 };
-//             print!(
-//                 " ((x: {}, y: {}), {:?}, {})",
-//                 neighbor.0.x, neighbor.0.y, neighbor.1, weight
-//             );
-//         }
-//         print!("\nIncoming: ");
-//         for (neighbor, n2, weight) in maze.edges_directed(node, Incoming) {
-//             assert!(n2 == node);
-//             // let neighbor = if n1 == node { n2 } else { n1 };
-//             print!(
-//                 " ((x: {}, y: {}), {:?}, {})",
-//                 neighbor.0.x, neighbor.0.y, neighbor.1, weight
-//             );
-//         }
-//         assert!(maze
-//             .edges_directed(node, Outgoing)
-//             .all(|i| !maze.edges_directed(node, Incoming).any(|j| i == j)));
-//         println!();
-//     }
-// }
-
-// fn display_cells(cells: &Array2<Cell>, start: Vec2<usize>, end: Vec2<usize>) {
-//     println!();
-//     for (y, row) in cells.axis_iter(Axis(0)).enumerate() {
-//         for (x, cell) in row.iter().enumerate() {
-//             let idx = Vec2 { x, y };
-//             print!(
-//                 "{}",
-//                 if start == idx {
-//                     START
-//                 } else if end == idx {
-//                     END
-//                 } else {
-//                     match cell {
-//                         Cell::Empty => EMPTY,
-//                         Cell::Wall => WALL,
-//                     }
-//                 }
-//                 .to_string()
-//             )
-//         }
-//         println!();
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLES: [&str; 2] = [
+        "\
+###############
+#.......#....E#
+#.#.###.#.###.#
+#.....#.#...#.#
+#.###.#####.#.#
+#.#.#.......#.#
+#.#.#####.###.#
+#...........#.#
+###.#.#####.#.#
+#...#.....#.#.#
+#.#.#.###.#.#.#
+#.....#...#.#.#
+#.###.#.#.#.#.#
+#S..#.....#...#
+###############",
+        "\
+#################
+#...#...#...#..E#
+#.#.#.#.#.#.#.#.#
+#.#.#.#...#...#.#
+#.#.#.#.###.#.#.#
+#...#.#.#.....#.#
+#.#.#.#.#.#####.#
+#.#...#.#.#.....#
+#.#.#####.#.###.#
+#.#.#.......#...#
+#.#.###.#####.###
+#.#.#...#.....#.#
+#.#.#.#####.###.#
+#.#.#.........#.#
+#.#.#.#########.#
+#S#.............#
+#################",
+    ];
+
+    fn maze(example: &str, costs: CostModel) -> Maze {
+        let (cells, start, end) = parse_maze(example.lines().map(str::to_string));
+        Maze::new(&cells, start, end, costs)
+    }
+
+    // A* only finds the lowest score if the heuristic never overestimates
+    fn assert_astar_matches_dijkstra(maze: &Maze) -> u64 {
+        let (score, _) = maze.shortest_path().unwrap();
+        let node_scores = maze.node_scores();
+        let lowest = node_scores
+            .iter()
+            .filter(|((index, _), _)| *index == maze.end)
+            .map(|(_, score)| *score)
+            .min();
+        assert_eq!(Some(score), lowest);
+        score
+    }
+
+    #[test]
+    fn reindeer_costs() {
+        let scores = EXAMPLES.map(|example| {
+            let maze = maze(example, REINDEER_COSTS);
+            let score = assert_astar_matches_dijkstra(&maze);
+            (score, OptimalPaths::new(&maze).unwrap().tiles.len())
+        });
+        assert_eq!(scores, [(7036, 45), (11048, 64)]);
+    }
+
+    #[test]
+    fn u_turns_and_start_directions() {
+        let costs = [
+            CostModel {
+                step: 1,
+                turn: 1000,
+                u_turn: Some(1500),
+                start_directions: &[Direction::Left, Direction::Down],
+            },
+            CostModel {
+                step: 3,
+                turn: 10,
+                u_turn: Some(0),
+                start_directions: &DIRECTIONS,
+            },
+            CostModel {
+                step: 1,
+                turn: 1000,
+                u_turn: Some(1000),
+                start_directions: &[Direction::Up, Direction::Right],
+            },
+        ];
+        for example in EXAMPLES {
+            for costs in costs.clone() {
+                let maze = maze(example, costs);
+                let score = assert_astar_matches_dijkstra(&maze);
+                assert_eq!(OptimalPaths::new(&maze).unwrap().score, score);
+            }
+        }
+        // Facing up from the start skips the first turn of both examples
+        let scores =
+            EXAMPLES.map(|example| assert_astar_matches_dijkstra(&maze(example, costs[2].clone())));
+        assert_eq!(scores, [6036, 10048]);
+    }
+}
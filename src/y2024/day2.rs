@@ -1,48 +1,86 @@
 use crate::{get_text_file, SolutionResult};
-use itertools::Itertools;
+use rustc_hash::FxHashMap;
 use std::{
-    cmp::Ordering,
     fs::File,
     io::{BufRead, BufReader},
+    ops::RangeInclusive,
 };
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/2/input";
 
-const MIN_LEVEL_DIFF: i32 = 1;
-const MAX_LEVEL_DIFF: i32 = 3;
+const LEVEL_DIFF: RangeInclusive<i32> = 1..=3;
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let safe_reports = iter_input(file)
-        .filter(|levels| {
-            if levels.len() <= 1 {
-                return true;
-            }
-            let sign = (levels[1] - levels[0]).signum();
-            if sign == 0 {
-                return false;
-            }
-            if levels
-                .into_iter()
-                .tuple_windows::<(_, _)>()
-                .map(|(l1, l2)| sign * (l2 - l1))
-                .all(|diff| (MIN_LEVEL_DIFF..=MAX_LEVEL_DIFF).contains(&diff))
-            {
-                return true;
+// Finds the fewest levels to remove so that the remaining levels all increase or all
+// decrease by a difference within the bounds
+struct SafetyAnalyzer {
+    diff: RangeInclusive<i32>,
+}
+
+impl SafetyAnalyzer {
+    // Indices of the levels to remove, empty for a safe report
+    fn removals(&self, levels: &[i32]) -> Vec<usize> {
+        let kept = [1, -1]
+            .into_iter()
+            .map(|sign| self.longest_chain(levels, sign))
+            .max_by_key(Vec::len)
+            .unwrap_or_default();
+        let mut kept = kept.into_iter().peekable();
+        (0..levels.len())
+            .filter(|index| kept.next_if_eq(index).is_none())
+            .collect()
+    }
+
+    // Longest subsequence with each step `sign * diff` within the bounds. Only levels
+    // within the bounds of the current one can precede it, so the best chain ending at
+    // each value gives O(n * bounds width).
+    fn longest_chain(&self, levels: &[i32], sign: i32) -> Vec<usize> {
+        let mut previous = vec![None; levels.len()];
+        let mut lengths = vec![0; levels.len()];
+        // Index of the longest chain ending with a level
+        let mut best: FxHashMap<i32, usize> = FxHashMap::default();
+        for (index, level) in levels.iter().enumerate() {
+            previous[index] = self
+                .diff
+                .clone()
+                .filter_map(|diff| best.get(&(level - sign * diff)).copied())
+                .max_by_key(|before| lengths[*before]);
+            lengths[index] = previous[index].map_or(1, |before| lengths[before] + 1);
+            let entry = best.entry(*level).or_insert(index);
+            if lengths[*entry] < lengths[index] {
+                *entry = index;
             }
-            false
-        })
-        .count() as i64;
+        }
+
+        let mut chain = Vec::new();
+        let mut last = (0..levels.len()).max_by_key(|index| lengths[*index]);
+        while let Some(index) = last {
+            chain.push(index);
+            last = previous[index];
+        }
+        chain.reverse();
+        chain
+    }
 
-    Ok(safe_reports.to_string())
+    fn is_safe(&self, levels: &[i32], max_removals: usize) -> bool {
+        self.removals(levels).len() <= max_removals
+    }
 }
 
-pub fn part_2() -> SolutionResult {
+pub fn part_1() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
+    Ok(count_safe(file, 0).to_string())
+}
 
-    let safe_reports = iter_input(file).filter(is_safe_with_tolerance).count() as i64;
+pub fn part_2() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    Ok(count_safe(file, 1).to_string())
+}
 
-    Ok(safe_reports.to_string())
+fn count_safe(file: File, max_removals: usize) -> usize {
+    let analyzer = SafetyAnalyzer { diff: LEVEL_DIFF };
+    iter_input(file)
+        .filter(|levels| analyzer.is_safe(levels, max_removals))
+        .count()
 }
 
 fn iter_input(file: File) -> impl Iterator<Item = Vec<i32>> {
@@ -53,55 +91,3 @@ fn iter_input(file: File) -> impl Iterator<Item = Vec<i32>> {
             .collect()
     })
 }
-
-fn is_safe_with_tolerance(levels: &Vec<i32>) -> bool {
-    let is_good = |diff| (MIN_LEVEL_DIFF..=MAX_LEVEL_DIFF).contains(&diff);
-
-    if levels.len() < 2 {
-        return true;
-    }
-    let mut levels_diff: Vec<_> = levels
-        .into_iter()
-        .tuple_windows::<(_, _)>()
-        .map(|(l1, l2)| l2 - l1)
-        .collect();
-
-    let mut positives = 0;
-    let mut negatives = 0;
-    for diff in levels_diff.iter() {
-        match diff.cmp(&0) {
-            Ordering::Less => negatives += 1,
-            Ordering::Greater => positives += 1,
-            Ordering::Equal => {}
-        };
-    }
-    let sign = if positives >= negatives { 1 } else { -1 };
-    levels_diff.iter_mut().for_each(|diff| *diff = sign * *diff);
-
-    let mut idx = 0;
-    let mut tolerate = true;
-    while idx < levels_diff.len() {
-        let diff = levels_diff[idx];
-        if !is_good(diff) {
-            if !tolerate {
-                return false;
-            }
-
-            let tolerate_left = idx == 0 || is_good(diff + levels_diff[idx - 1]);
-            let tolerate_right =
-                idx + 1 == levels_diff.len() || is_good(diff + levels_diff[idx + 1]);
-
-            if tolerate_right {
-                idx += 2;
-            } else if tolerate_left {
-                idx += 1;
-            } else {
-                return false;
-            }
-            tolerate = false;
-        } else {
-            idx += 1;
-        }
-    }
-    true
-}
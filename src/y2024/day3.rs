@@ -1,7 +1,11 @@
-use crate::{get_text_file, SolutionResult};
-use itertools::Itertools;
-use regex::Regex;
-use std::io::Read;
+use crate::{
+    get_text_file,
+    math::{checked_product, checked_sum, to_answer, Answer},
+    parser::{Parser, Rule, Spanned},
+    AnyError, SolutionResult,
+};
+use num::Zero;
+use std::{fmt::Write, io::Read};
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/3/input";
 
@@ -16,27 +20,87 @@ enum Instruction {
     Dont,
 }
 
-#[derive(PartialEq, Eq, Debug)]
-enum Token {
-    Number(i64),
-    Identifier(String),
-    OpenParen,
-    CloseParen,
-    Comma,
+const GRAMMAR: [Rule<Instruction>; 3] = [
+    Rule {
+        name: MUL,
+        arity: 2,
+        build: |args| Instruction::Mul(args[0], args[1]),
+    },
+    Rule {
+        name: DO,
+        arity: 0,
+        build: |_| Instruction::Do,
+    },
+    Rule {
+        name: DONT,
+        arity: 0,
+        build: |_| Instruction::Dont,
+    },
+];
+
+struct Effect {
+    enabled: bool,
+    contribution: Answer,
+}
+
+trait Interpreter {
+    fn execute(&mut self, instruction: &Instruction) -> Result<Effect, AnyError>;
+}
+
+// Every mul counts, conditionals are ignored
+struct Unconditional;
+
+impl Interpreter for Unconditional {
+    fn execute(&mut self, instruction: &Instruction) -> Result<Effect, AnyError> {
+        Ok(Effect {
+            enabled: true,
+            contribution: match instruction {
+                Instruction::Mul(lhs, rhs) => mul(*lhs, *rhs)?,
+                Instruction::Do | Instruction::Dont => Answer::zero(),
+            },
+        })
+    }
+}
+
+struct Conditional {
+    enable_mul: bool,
+}
+
+impl Interpreter for Conditional {
+    fn execute(&mut self, instruction: &Instruction) -> Result<Effect, AnyError> {
+        let contribution = match instruction {
+            Instruction::Mul(lhs, rhs) if self.enable_mul => mul(*lhs, *rhs)?,
+            Instruction::Mul(..) => {
+                return Ok(Effect {
+                    enabled: false,
+                    contribution: Answer::zero(),
+                })
+            }
+            Instruction::Do => {
+                self.enable_mul = true;
+                Answer::zero()
+            }
+            Instruction::Dont => {
+                self.enable_mul = false;
+                Answer::zero()
+            }
+        };
+        Ok(Effect {
+            enabled: true,
+            contribution,
+        })
+    }
 }
 
 pub fn part_1() -> SolutionResult {
     let mut text = String::new();
     get_text_file(INPUT_URL)?.read_to_string(&mut text)?;
-    let re = Regex::new(r"mul\(([0-9]+),([0-9]+)\)")?;
 
-    let result: i64 = re
-        .captures_iter(&text)
-        .map(|c| {
-            let (_, [lhs, rhs]) = c.extract();
-            lhs.parse::<i64>().unwrap() * rhs.parse::<i64>().unwrap()
-        })
-        .sum();
+    let result = checked_sum(
+        evaluate(&text, &mut Unconditional)?
+            .into_iter()
+            .map(|(_, effect)| effect.contribution),
+    )?;
 
     Ok(result.to_string())
 }
@@ -45,103 +109,54 @@ pub fn part_2() -> SolutionResult {
     let mut text = String::new();
     get_text_file(INPUT_URL)?.read_to_string(&mut text)?;
 
-    let mut tokens: Vec<Token> = Vec::new();
-    let mut char_iter = text.chars().peekable();
-
-    while let Some(char) = char_iter.next() {
-        tokens.push(match char {
-            '(' => Token::OpenParen,
-            ')' => Token::CloseParen,
-            ',' => Token::Comma,
-            ch if ch.is_digit(10) => {
-                let mut num = ch.to_string();
-                while let Some(peek) = char_iter.peek() {
-                    if peek.is_digit(10) {
-                        num.push(char_iter.next().unwrap());
-                    } else {
-                        break;
-                    }
-                }
-                Token::Number(num.parse().unwrap())
-            }
-            ch if ch == '\'' || ch.is_ascii_alphabetic() => {
-                let mut ident = ch.to_string();
-                while let Some(peek) = char_iter.peek() {
-                    if peek == &'\'' || peek.is_ascii_alphabetic() {
-                        ident.push(char_iter.next().unwrap());
-                    } else {
-                        break;
-                    }
-                }
-                Token::Identifier(ident)
-            }
-            _ => continue,
-        })
-    }
+    let result = checked_sum(
+        evaluate(&text, &mut Conditional { enable_mul: true })?
+            .into_iter()
+            .map(|(_, effect)| effect.contribution),
+    )?;
+
+    Ok(result.to_string())
+}
+
+pub fn explain(part: u32) -> SolutionResult {
+    let mut text = String::new();
+    get_text_file(INPUT_URL)?.read_to_string(&mut text)?;
 
-    let mut instructions: Vec<Instruction> = Vec::new();
-    let mut tokens_iter = tokens.iter();
-    while let Some(token) = tokens_iter.next() {
-        if let Token::Identifier(ident) = token {
-            instructions.push(if ident.ends_with(MUL) {
-                let peeks = tokens_iter.clone().take(5).collect_vec();
-                if peeks.len() < 5 {
-                    continue;
-                }
-                if let (
-                    Token::OpenParen,
-                    Token::Number(lhs),
-                    Token::Comma,
-                    Token::Number(rhs),
-                    Token::CloseParen,
-                ) = (peeks[0], peeks[1], peeks[2], peeks[3], peeks[4])
-                {
-                    Instruction::Mul(*lhs, *rhs)
-                } else {
-                    continue;
-                }
-            } else if ident.ends_with(DO) {
-                let peeks = tokens_iter.clone().take(2).collect_vec();
-                if peeks.len() < 2 {
-                    continue;
-                }
-                if let (Token::OpenParen, Token::CloseParen) = (peeks[0], peeks[1]) {
-                    Instruction::Do
-                } else {
-                    continue;
-                }
-            } else if ident.ends_with(DONT) {
-                let peeks = tokens_iter.clone().take(2).collect_vec();
-                if peeks.len() < 2 {
-                    continue;
-                }
-                if let (Token::OpenParen, Token::CloseParen) = (peeks[0], peeks[1]) {
-                    Instruction::Dont
-                } else {
-                    continue;
-                }
-            } else {
-                continue;
-            });
-        }
+    let evaluation = match part {
+        1 => evaluate(&text, &mut Unconditional)?,
+        2 => evaluate(&text, &mut Conditional { enable_mul: true })?,
+        _ => return Err("Part does not exist".into()),
+    };
+    let mut explanation = String::new();
+    for (instruction, effect) in evaluation {
+        let status = if effect.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        };
+        writeln!(
+            explanation,
+            "{:>6} {:<12} {status:<8} +{}",
+            instruction.span.start,
+            &text[instruction.span.clone()],
+            effect.contribution
+        )?;
     }
+    Ok(explanation)
+}
 
-    let mut enable_mul = true;
-    let result: i64 = instructions
-        .iter()
-        .filter_map(|instruction| {
-            match instruction {
-                Instruction::Mul(lhs, rhs) => {
-                    if enable_mul {
-                        return Some(lhs * rhs);
-                    }
-                }
-                Instruction::Do => enable_mul = true,
-                Instruction::Dont => enable_mul = false,
-            }
-            None
+fn evaluate<T: Interpreter>(
+    text: &str,
+    interpreter: &mut T,
+) -> Result<Vec<(Spanned<Instruction>, Effect)>, AnyError> {
+    Parser::new(text, &GRAMMAR)
+        .map(|instruction| {
+            let effect = interpreter.execute(&instruction.value)?;
+            Ok((instruction, effect))
         })
-        .sum();
+        .collect()
+}
 
-    Ok(result.to_string())
+fn mul(lhs: i64, rhs: i64) -> Result<Answer, AnyError> {
+    checked_product([to_answer(lhs)?, to_answer(rhs)?])
 }
@@ -2,50 +2,199 @@ use crate::{get_text_file, math::Vec2, SolutionResult};
 use itertools::Itertools;
 use ndarray::prelude::*;
 use std::{
+    fmt::Write,
     fs::File,
     io::{BufRead, BufReader},
 };
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/4/input";
 
-const SOURCE_CHAR: char = 'X';
-const OTHER_CHARS: &str = "MAS";
+const WORD: &str = "XMAS";
+const X_MAS: [&str; 3] = ["M.S", ".A.", "M.S"];
+const WILDCARD: char = '.';
 
-const FIRST_CHAR: char = 'M';
-const MIDDLE_CHAR: char = 'A';
-const LAST_CHAR: char = 'S';
+// Non-wildcard cells by offset from the stencil's top left corner
+type Cells = Vec<(Vec2<isize>, char)>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Orientation {
+    reflected: bool,
+    quarter_turns: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Match {
+    // Cell of the stencil's first letter
+    position: Vec2<usize>,
+    // Unit step along the stencil's rows as they read in the grid
+    direction: Vec2<isize>,
+    orientation: Orientation,
+}
+
+#[derive(Clone, Debug)]
+struct Variant {
+    orientation: Orientation,
+    direction: Vec2<isize>,
+    // Offset of the first letter
+    first: Vec2<isize>,
+    cells: Cells,
+}
+
+#[derive(Clone, Debug)]
+struct Pattern {
+    // Distinct orientations of the non-wildcard cells
+    variants: Vec<Variant>,
+}
+
+impl Pattern {
+    // Rows of a 2D stencil with `WILDCARD` matching anything, in all rotations and
+    // reflections
+    fn stencil(rows: &[&str]) -> Self {
+        Self::stencil_along(rows, Vec2 { x: 1, y: 0 })
+    }
+
+    // Same as stencil, with the rows reading along the given unit step
+    fn stencil_along(rows: &[&str], reading: Vec2<isize>) -> Self {
+        let cells = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter(|(_, ch)| *ch != WILDCARD)
+                    .map(move |(x, ch)| {
+                        (
+                            Vec2 {
+                                x: x as isize,
+                                y: y as isize,
+                            },
+                            ch,
+                        )
+                    })
+            })
+            .collect_vec();
+
+        let corner = Vec2 {
+            x: rows
+                .iter()
+                .map(|row| row.chars().count())
+                .max()
+                .unwrap_or(0) as isize
+                - 1,
+            y: rows.len() as isize - 1,
+        };
+
+        let mut variants: Vec<Variant> = Vec::new();
+        for reflected in [false, true] {
+            for quarter_turns in 0..4 {
+                let orientation = Orientation {
+                    reflected,
+                    quarter_turns,
+                };
+                let (top_left, bottom_right) = (
+                    orientation.apply(Vec2 { x: 0, y: 0 }),
+                    orientation.apply(corner),
+                );
+                let origin = Vec2 {
+                    x: top_left.x.min(bottom_right.x),
+                    y: top_left.y.min(bottom_right.y),
+                };
+                let mut oriented = cells
+                    .iter()
+                    .map(|(offset, ch)| (orientation.apply(*offset) - origin, *ch))
+                    .collect_vec();
+                let Some(first) = oriented.first().map(|(offset, _)| *offset) else {
+                    continue;
+                };
+                oriented.sort_unstable_by_key(|(offset, _)| (offset.y, offset.x));
+                if variants.iter().all(|other| other.cells != oriented) {
+                    variants.push(Variant {
+                        orientation,
+                        direction: orientation.apply(reading),
+                        first,
+                        cells: oriented,
+                    });
+                }
+            }
+        }
+        Self { variants }
+    }
+
+    // Straight and diagonal reading directions
+    fn word(word: &str) -> Self {
+        let diagonal = word
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| {
+                (0..word.len())
+                    .map(|x| if x == i { ch } else { WILDCARD })
+                    .collect::<String>()
+            })
+            .collect_vec();
+        let mut pattern = Self::stencil(&[word]);
+        let diagonal = Self::stencil_along(
+            &diagonal.iter().map(String::as_str).collect_vec(),
+            Vec2 { x: 1, y: 1 },
+        );
+        for variant in diagonal.variants {
+            // A single letter reads the same in every direction
+            if pattern
+                .variants
+                .iter()
+                .all(|other| other.cells != variant.cells)
+            {
+                pattern.variants.push(variant);
+            }
+        }
+        pattern
+    }
+
+    fn find(&self, grid: &Array2<char>) -> Vec<Match> {
+        grid.indexed_iter()
+            .flat_map(|(index, _)| {
+                let position = Vec2::from_index_tuple(index);
+                self.variants
+                    .iter()
+                    .filter(move |variant| {
+                        variant.cells.iter().all(|(offset, ch)| {
+                            position
+                                .signed_add(*offset)
+                                .and_then(|index| grid.get(index))
+                                == Some(ch)
+                        })
+                    })
+                    .map(move |variant| Match {
+                        position: position.signed_add(variant.first).unwrap(),
+                        direction: variant.direction,
+                        orientation: variant.orientation,
+                    })
+            })
+            .collect()
+    }
+}
+
+impl Orientation {
+    fn apply(&self, offset: Vec2<isize>) -> Vec2<isize> {
+        let mut offset = if self.reflected {
+            Vec2 {
+                x: -offset.x,
+                y: offset.y,
+            }
+        } else {
+            offset
+        };
+        for _ in 0..self.quarter_turns {
+            offset = offset.rotate_90();
+        }
+        offset
+    }
+}
 
 pub fn part_1() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
     let matrix = read_input(file);
-    let mut positions = (1..=3).map(|i: isize| Vec2 { x: i, y: i }).collect_vec();
-    let rotations = (0..8)
-        .map(|_| {
-            positions = positions.iter().map(|arg0| rotate_45(*arg0)).collect();
-            positions.clone()
-        })
-        .collect_vec();
-    let word_found = |idx: Vec2<usize>, rotation: &Vec<Vec2<isize>>| {
-        OTHER_CHARS
-            .chars()
-            .zip(rotation.iter())
-            .all(|(ch_desired, pos)| {
-                idx.signed_add(*pos)
-                    .and_then(|shift_idx| matrix.get(shift_idx))
-                    .map_or(false, |ch_actual| *ch_actual == ch_desired)
-            })
-    };
 
-    let occurences: i64 = matrix
-        .indexed_iter()
-        .filter(|(_, ch)| **ch == SOURCE_CHAR)
-        .map(|(idx, _)| {
-            rotations
-                .iter()
-                .filter(|r| word_found(Vec2::from_index_tuple(idx), *r))
-                .count() as i64
-        })
-        .sum();
+    let occurences = Pattern::word(WORD).find(&matrix).len();
 
     Ok(occurences.to_string())
 }
@@ -53,38 +202,66 @@ pub fn part_1() -> SolutionResult {
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
     let matrix = read_input(file);
-    let mut corner = Vec2 { x: 1, y: 1 };
-    let corner_pairs = (0..2)
-        .map(|_| {
-            corner = rotate_90(corner);
-            (corner, -corner)
-        })
-        .collect_vec();
-    let word_found = |idx: Vec2<usize>, (pos_1, pos_2): (_, _)| {
-        idx.signed_add(pos_1)
-            .zip(idx.signed_add(pos_2))
-            .and_then(|(idx_1, idx_2)| matrix.get(idx_1).zip(matrix.get(idx_2)))
-            .map_or(false, |(ch_1, ch_2)| {
-                matches!(
-                    (*ch_1, *ch_2),
-                    (FIRST_CHAR, LAST_CHAR) | (LAST_CHAR, FIRST_CHAR)
-                )
-            })
-    };
 
-    let occurences = matrix
-        .indexed_iter()
-        .filter(|(idx, ch)| {
-            **ch == MIDDLE_CHAR
-                && corner_pairs
-                    .iter()
-                    .all(|pos_pair| word_found(Vec2::from_index_tuple(*idx), *pos_pair))
-        })
-        .count();
+    let occurences = Pattern::stencil(&X_MAS).find(&matrix).len();
 
     Ok(occurences.to_string())
 }
 
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let matrix = read_input(file);
+
+    let pattern = match part {
+        1 => Pattern::word(WORD),
+        2 => Pattern::stencil(&X_MAS),
+        _ => return Err("Part does not exist".into()),
+    };
+    let mut explanation = String::new();
+    for Match {
+        position,
+        direction,
+        orientation,
+    } in pattern.find(&matrix)
+    {
+        write!(
+            explanation,
+            "({}, {}) reading {}",
+            position.x,
+            position.y,
+            direction_name(direction)
+        )?;
+        if part == 2 {
+            write!(
+                explanation,
+                ", rotated {} degrees{}",
+                u32::from(orientation.quarter_turns) * 90,
+                if orientation.reflected {
+                    ", reflected"
+                } else {
+                    ""
+                }
+            )?;
+        }
+        writeln!(explanation)?;
+    }
+    Ok(explanation)
+}
+
+fn direction_name(direction: Vec2<isize>) -> &'static str {
+    match (direction.x, direction.y) {
+        (1, 0) => "right",
+        (1, 1) => "down-right",
+        (0, 1) => "down",
+        (-1, 1) => "down-left",
+        (-1, 0) => "left",
+        (-1, -1) => "up-left",
+        (0, -1) => "up",
+        (1, -1) => "up-right",
+        _ => "nowhere",
+    }
+}
+
 fn read_input(file: File) -> Array2<char> {
     let mut data = Vec::new();
     let rows = BufReader::new(file)
@@ -97,14 +274,53 @@ fn read_input(file: File) -> Array2<char> {
     Array2::from_shape_vec((rows, cols), data).unwrap()
 }
 
-fn rotate_45(Vec2 { x, y }: Vec2<isize>) -> Vec2<isize> {
-    let scale = isize::max(x.abs(), y.abs());
-    Vec2 {
-        x: (x - y).signum() * scale,
-        y: (x + y).signum() * scale,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Array2<char> {
+        Array2::from_shape_vec(
+            (rows.len(), rows[0].len()),
+            rows.iter().flat_map(|row| row.chars()).collect(),
+        )
+        .unwrap()
+    }
+
+    fn matches(pattern: &Pattern, rows: &[&str]) -> Vec<(Vec2<usize>, &'static str)> {
+        pattern
+            .find(&grid(rows))
+            .into_iter()
+            .map(|found| (found.position, direction_name(found.direction)))
+            .sorted_by_key(|(position, name)| (position.y, position.x, *name))
+            .collect()
     }
-}
 
-fn rotate_90(Vec2 { x, y }: Vec2<isize>) -> Vec2<isize> {
-    Vec2 { x: -y, y: x }
+    #[test]
+    fn word_directions() {
+        let pattern = Pattern::word(WORD);
+        assert_eq!(
+            matches(&pattern, &["XMAS.", "MM...", "A.A..", "S..S."]),
+            [
+                (Vec2 { x: 0, y: 0 }, "down"),
+                (Vec2 { x: 0, y: 0 }, "down-right"),
+                (Vec2 { x: 0, y: 0 }, "right"),
+            ]
+        );
+        assert_eq!(
+            matches(&pattern, &["SAMX", "..M.", ".A..", "S..."]),
+            [
+                (Vec2 { x: 3, y: 0 }, "down-left"),
+                (Vec2 { x: 3, y: 0 }, "left")
+            ]
+        );
+    }
+
+    #[test]
+    fn stencil_first_letter() {
+        let pattern = Pattern::stencil(&X_MAS);
+        let found = pattern.find(&grid(&["S.M", ".A.", "S.M"]));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].position, Vec2 { x: 2, y: 2 });
+        assert_eq!(direction_name(found[0].direction), "left");
+    }
 }
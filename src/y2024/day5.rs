@@ -1,66 +1,179 @@
-use crate::{get_text_file, utils::FxDiGraphMap, SolutionResult};
+use crate::{
+    get_text_file,
+    math::{to_answer, try_checked_sum},
+    utils::FxDiGraphMap,
+    AnyError, SolutionResult,
+};
 use itertools::Itertools;
-use petgraph::{algo::toposort, visit::NodeFiltered};
+use petgraph::algo::tarjan_scc;
 use rustc_hash::FxHashSet;
 use std::{
+    cmp::Ordering,
+    fmt::Write,
     fs::File,
     io::{BufRead, BufReader},
 };
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/5/input";
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let (graph, sequences) = read_input(file);
+// (before, after) page pairs of the a|b rules
+struct RuleSet {
+    rules: FxHashSet<(i64, i64)>,
+}
+
+impl RuleSet {
+    fn compare(&self, a: i64, b: i64) -> Ordering {
+        if self.rules.contains(&(a, b)) {
+            Ordering::Less
+        } else if self.rules.contains(&(b, a)) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    fn first_violation(&self, update: &[i64]) -> Option<(i64, i64)> {
+        update
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| (*b, *a))
+            .find(|rule| self.rules.contains(rule))
+    }
+
+    // Whether compare is a total order on the update. Every pair of pages needs a rule, and
+    // no two pages may precede the same number of others, which rules between every pair
+    // only allow when they have no cycle
+    fn totally_orders(&self, update: &[i64]) -> bool {
+        let mut preceded = vec![false; update.len()];
+        update.iter().all(|page| {
+            let count = update
+                .iter()
+                .filter(|other| *other != page && self.rules.contains(&(*page, **other)))
+                .count();
+            !std::mem::replace(&mut preceded[count], true)
+        }) && update
+            .iter()
+            .tuple_combinations()
+            .all(|(a, b)| self.compare(*a, *b) != Ordering::Equal)
+    }
 
-    let result: i64 = sequences
-        .into_iter()
-        .filter_map(|values| {
-            let value_set: FxHashSet<i64> = values.clone().into_iter().collect();
-            let filtered = NodeFiltered::from_fn(&graph, |n| value_set.contains(&n));
+    // Reports the rules and pages of a cycle among the update's pages
+    fn validate(&self, update: &[i64]) -> Result<(), AnyError> {
+        if self.totally_orders(update) {
+            return Ok(());
+        }
+        let pages: FxHashSet<i64> = update.iter().copied().collect();
+        let graph = FxDiGraphMap::<i64, ()>::from_edges(
+            self.rules
+                .iter()
+                .filter(|(a, b)| pages.contains(a) && pages.contains(b)),
+        );
+        if let Some(cycle) = tarjan_scc(&graph)
+            .into_iter()
+            .find(|scc| scc.len() > 1 || graph.contains_edge(scc[0], scc[0]))
+        {
+            let members: FxHashSet<i64> = cycle.iter().copied().collect();
+            let conflicting = self
+                .rules
+                .iter()
+                .filter(|(a, b)| members.contains(a) && members.contains(b))
+                .sorted()
+                .map(|(a, b)| format!("{a}|{b}"))
+                .join(", ");
+            return Err(format!(
+                "Rules {conflicting} form a cycle over pages {}",
+                cycle.iter().sorted().join(",")
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    // The standard sort may panic when compare is not a total order, so updates the rules
+    // leave partially ordered are only checked for the reason they can't be sorted
+    fn sort(&self, update: &[i64]) -> Result<Vec<i64>, AnyError> {
+        let mut sorted = update.to_vec();
+        if self.totally_orders(update) {
+            sorted.sort_by(|a, b| self.compare(*a, *b));
+        }
+        if self.first_violation(&sorted).is_none() {
+            return Ok(sorted);
+        }
+        self.validate(update)?;
+        let (a, b) = update
+            .iter()
+            .tuple_combinations()
+            .find(|(a, b)| self.compare(**a, **b) == Ordering::Equal)
+            .ok_or("Rules do not order the update")?;
+        Err(format!("Rules give no order between pages {a} and {b}").into())
+    }
+}
 
-            let sorted = toposort(&filtered, None).unwrap();
+pub fn part_1() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let (rule_set, updates) = read_input(file);
 
-            if *values == sorted {
-                let middle_idx = values.len() / 2 + values.len() % 2 - 1;
-                values.get(middle_idx).map(i64::clone)
-            } else {
-                None
+    let result = try_checked_sum(updates.iter().filter_map(
+        |update| match rule_set.validate(update) {
+            Err(error) => Some(Err(error)),
+            Ok(()) if rule_set.first_violation(update).is_none() => {
+                Some(middle_page(update).and_then(to_answer))
             }
-        })
-        .sum();
+            Ok(()) => None,
+        },
+    ))?;
 
     Ok(result.to_string())
 }
 
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
-    let (graph, sequences) = read_input(file);
+    let (rule_set, updates) = read_input(file);
 
-    let result: i64 = sequences
-        .into_iter()
-        .filter_map(|values| {
-            let value_set: FxHashSet<i64> = values.clone().into_iter().collect();
-            let filtered = NodeFiltered::from_fn(&graph, |n| value_set.contains(&n));
+    let result = try_checked_sum(
+        updates
+            .iter()
+            .filter(|update| rule_set.first_violation(update).is_some())
+            .map(|update| to_answer(middle_page(&rule_set.sort(update)?)?)),
+    )?;
 
-            let sorted = toposort(&filtered, None).unwrap();
+    Ok(result.to_string())
+}
 
-            if *values != sorted {
-                let middle_idx = values.len() / 2 + values.len() % 2 - 1;
-                sorted.get(middle_idx).map(i64::clone)
-            } else {
-                None
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let (rule_set, updates) = read_input(file);
+
+    let mut explanation = String::new();
+    for update in updates {
+        write!(explanation, "{}: ", update.iter().join(","))?;
+        match rule_set.first_violation(&update) {
+            None => writeln!(explanation, "in order")?,
+            Some((a, b)) if part == 1 => {
+                rule_set.validate(&update)?;
+                writeln!(explanation, "out of order, violates {a}|{b}")?
             }
-        })
-        .sum();
+            Some((a, b)) => writeln!(
+                explanation,
+                "out of order, violates {a}|{b}, sorted {}",
+                rule_set.sort(&update)?.iter().join(",")
+            )?,
+        }
+    }
+    Ok(explanation)
+}
 
-    Ok(result.to_string())
+fn middle_page(update: &[i64]) -> Result<i64, AnyError> {
+    update
+        .get(update.len().saturating_sub(1) / 2)
+        .copied()
+        .ok_or_else(|| "Empty update".into())
 }
 
-pub fn read_input(file: File) -> (FxDiGraphMap<i64, ()>, Vec<Vec<i64>>) {
+fn read_input(file: File) -> (RuleSet, Vec<Vec<i64>>) {
     let mut first_section = true;
-    let mut graph = FxDiGraphMap::<i64, ()>::new();
-    let mut sequences: Vec<Vec<i64>> = Vec::new();
+    let mut rules = FxHashSet::default();
+    let mut updates: Vec<Vec<i64>> = Vec::new();
 
     for line in BufReader::new(file).lines() {
         let line = line.unwrap();
@@ -72,11 +185,92 @@ pub fn read_input(file: File) -> (FxDiGraphMap<i64, ()>, Vec<Vec<i64>>) {
                 .map(|ch| ch.parse().unwrap())
                 .collect_tuple()
                 .unwrap();
-            graph.add_edge(v, u, ());
+            rules.insert((v, u));
         } else {
-            sequences.push(line.split(',').map(|ch| ch.parse().unwrap()).collect());
+            updates.push(line.split(',').map(|ch| ch.parse().unwrap()).collect());
+        }
+    }
+
+    (RuleSet { rules }, updates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(rules: impl IntoIterator<Item = (i64, i64)>) -> RuleSet {
+        RuleSet {
+            rules: rules.into_iter().collect(),
         }
     }
 
-    (graph, sequences)
+    // Rules between every pair of the pages, in increasing order
+    fn total_rules(pages: std::ops::Range<i64>) -> impl Iterator<Item = (i64, i64)> {
+        pages.tuple_combinations()
+    }
+
+    // Longer than the insertion sort std uses for short slices, in a fixed shuffled order
+    fn shuffled_update() -> Vec<i64> {
+        (0..31).map(|i| 10 + (i * 17) % 31).collect()
+    }
+
+    #[test]
+    fn sort_total_rules() {
+        let rule_set = rules(total_rules(10..50));
+        let sorted = rule_set.sort(&shuffled_update()).unwrap();
+        assert_eq!(sorted, (10..41).collect_vec());
+        assert_eq!(middle_page(&sorted).unwrap(), 25);
+        assert!(rule_set.validate(&shuffled_update()).is_ok());
+    }
+
+    #[test]
+    fn sort_cyclic_rules() {
+        let flipped =
+            total_rules(10..41).map(|rule| if rule == (10, 12) { (12, 10) } else { rule });
+        let rule_set = rules(flipped);
+        let error = rule_set.sort(&shuffled_update()).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Rules 10|11, 11|12, 12|10 form a cycle over pages 10,11,12"
+        );
+        // Part 1 rejects the cycle too, though the update is never sorted there
+        assert_eq!(
+            rule_set
+                .validate(&shuffled_update())
+                .unwrap_err()
+                .to_string(),
+            error
+        );
+
+        // A cycle through pages missing from the update doesn't affect it
+        let rule_set = rules(total_rules(10..41).chain([(50, 51), (51, 52), (52, 50)]));
+        assert_eq!(
+            rule_set.sort(&shuffled_update()).unwrap(),
+            (10..41).collect_vec()
+        );
+
+        let rule_set = rules((0..40).map(|i| (10 + i, 10 + (i + 1) % 40)));
+        let error = rule_set
+            .sort(&(10..50).rev().collect_vec())
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("form a cycle over pages 10,11,12"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn sort_partial_rules() {
+        let rule_set = rules([(40, 10), (25, 11), (11, 30), (12, 13), (33, 12)]);
+        let update = shuffled_update();
+        assert!(rule_set.validate(&update).is_ok());
+        assert_eq!(
+            rule_set.sort(&update).unwrap_err().to_string(),
+            "Rules give no order between pages 10 and 27"
+        );
+        // Already in order needs no comparisons between unordered pages
+        let in_order = [40, 10, 25, 11, 30];
+        assert_eq!(rule_set.sort(&in_order).unwrap(), in_order);
+    }
 }
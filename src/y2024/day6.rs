@@ -1,5 +1,12 @@
-use crate::{get_text_file, math::Vec2, SolutionResult};
+use crate::{
+    get_text_file,
+    math::Vec2,
+    utils::BitSet,
+    visualize::{Frame, Glyph, Recorder, Rgb},
+    AnyError, SolutionResult,
+};
 use ndarray::prelude::*;
+use rustc_hash::FxHashSet;
 use std::{
     fs::File,
     io::{BufRead, BufReader},
@@ -13,8 +20,11 @@ const GUARD_UP: char = '^';
 const GUARD_DOWN: char = 'v';
 const GUARD_RIGHT: char = '>';
 const GUARD_LEFT: char = '<';
+const VISITED: char = 'X';
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+const EXIT: u32 = u32::MAX;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 enum Direction {
     Up,
     Right,
@@ -41,8 +51,25 @@ impl Direction {
             Self::Left => Self::Up,
         }
     }
-}
 
+    fn index(&self) -> usize {
+        match self {
+            Self::Up => 0,
+            Self::Right => 1,
+            Self::Down => 2,
+            Self::Left => 3,
+        }
+    }
+
+    fn unit_vec(&self) -> Vec2<isize> {
+        match self {
+            Self::Up => Vec2 { x: 0, y: -1 },
+            Self::Right => Vec2 { x: 1, y: 0 },
+            Self::Down => Vec2 { x: 0, y: 1 },
+            Self::Left => Vec2 { x: -1, y: 0 },
+        }
+    }
+}
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 enum Cell {
@@ -68,48 +95,224 @@ impl Guard {
     }
 }
 
+#[derive(Clone, Debug)]
+enum WalkOutcome {
+    Exit {
+        // Last cell before leaving the map
+        position: Vec2<usize>,
+        steps: usize,
+    },
+    Cycle {
+        entry: Guard,
+        length: usize,
+        cells: Vec<Vec2<usize>>,
+    },
+}
+
+impl WalkOutcome {
+    fn exit_or_err(self) -> Result<(Vec2<usize>, usize), AnyError> {
+        match self {
+            Self::Exit { position, steps } => Ok((position, steps)),
+            Self::Cycle { entry, length, .. } => Err(format!(
+                "Guard walks in a cycle of {length} steps entered at ({}, {}) facing {:?}",
+                entry.position.x, entry.position.y, entry.direction
+            )
+            .into()),
+        }
+    }
+}
+
+struct JumpTable {
+    cols: usize,
+    // Cell where the guard stops in front of the next obstacle, per cell and direction
+    jumps: Vec<[u32; 4]>,
+}
+
+impl JumpTable {
+    fn new(cells: &Array2<Cell>) -> Self {
+        let (rows, cols) = cells.dim();
+        let mut jumps = vec![[EXIT; 4]; rows * cols];
+        let flat = |y: usize, x: usize| (y * cols + x) as u32;
+
+        for y in 0..rows {
+            let mut stop = EXIT;
+            for x in 0..cols {
+                if cells[(y, x)] == Cell::Obstacle {
+                    stop = flat(y, x + 1);
+                } else {
+                    jumps[flat(y, x) as usize][Direction::Left.index()] = stop;
+                }
+            }
+            let mut stop = EXIT;
+            for x in (0..cols).rev() {
+                if cells[(y, x)] == Cell::Obstacle {
+                    stop = x.checked_sub(1).map_or(EXIT, |x| flat(y, x));
+                } else {
+                    jumps[flat(y, x) as usize][Direction::Right.index()] = stop;
+                }
+            }
+        }
+        for x in 0..cols {
+            let mut stop = EXIT;
+            for y in 0..rows {
+                if cells[(y, x)] == Cell::Obstacle {
+                    stop = flat(y + 1, x);
+                } else {
+                    jumps[flat(y, x) as usize][Direction::Up.index()] = stop;
+                }
+            }
+            let mut stop = EXIT;
+            for y in (0..rows).rev() {
+                if cells[(y, x)] == Cell::Obstacle {
+                    stop = y.checked_sub(1).map_or(EXIT, |y| flat(y, x));
+                } else {
+                    jumps[flat(y, x) as usize][Direction::Down.index()] = stop;
+                }
+            }
+        }
+        Self { cols, jumps }
+    }
+
+    fn flat(&self, index: Vec2<usize>) -> u32 {
+        (index.y * self.cols + index.x) as u32
+    }
+
+    fn unflat(&self, flat: u32) -> Vec2<usize> {
+        Vec2 {
+            x: flat as usize % self.cols,
+            y: flat as usize / self.cols,
+        }
+    }
+
+    // Jumps obstacle to obstacle as if an extra obstacle was placed, visited holds
+    // the (cell, direction) states and must be empty
+    fn is_loop(&self, mut guard: Guard, obstacle: Vec2<usize>, visited: &mut BitSet) -> bool {
+        let mut flat = self.flat(guard.position.convert().unwrap());
+        loop {
+            if !visited.insert(flat as usize * 4 + guard.direction.index()) {
+                return true;
+            }
+            let position = self.unflat(flat);
+            let mut next = self.jumps[flat as usize][guard.direction.index()];
+            let stop = (next != EXIT).then(|| self.unflat(next));
+            let blocked = match guard.direction {
+                Direction::Up => {
+                    obstacle.x == position.x
+                        && obstacle.y < position.y
+                        && stop.is_none_or(|s| obstacle.y >= s.y)
+                }
+                Direction::Right => {
+                    obstacle.y == position.y
+                        && obstacle.x > position.x
+                        && stop.is_none_or(|s| obstacle.x <= s.x)
+                }
+                Direction::Down => {
+                    obstacle.x == position.x
+                        && obstacle.y > position.y
+                        && stop.is_none_or(|s| obstacle.y <= s.y)
+                }
+                Direction::Left => {
+                    obstacle.y == position.y
+                        && obstacle.x < position.x
+                        && stop.is_none_or(|s| obstacle.x >= s.x)
+                }
+            };
+            if blocked {
+                next = self.flat(obstacle.signed_add(-guard.direction.unit_vec()).unwrap());
+            }
+            if next == EXIT {
+                return false;
+            }
+            flat = next;
+            guard.direction = guard.direction.rotate();
+        }
+    }
+}
 
 pub fn part_1() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
     let (mut cells, mut guard) = read_input(file);
-    // println!("{:?}", cells);
     let mut visited = 0;
-    if !walk_guard(&mut cells, &mut guard, |_| visited += 1) {
-        panic!("Guard walking in cycle");
-    }
+    walk_guard(&mut cells, &mut guard, |_| visited += 1).exit_or_err()?;
     Ok(visited.to_string())
 }
 
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
     let (cells, guard) = read_input(file);
-    let mut visited = Vec::new();
-    if !walk_guard(&mut cells.clone(), &mut guard.clone(), |guard| {
-        visited.push(guard.position.convert().unwrap())
-    }) {
-        panic!("Guard walking in cycle");
-    };
-    let loops = visited
+
+    Ok(loop_obstacles(&cells, &guard)?.len().to_string())
+}
+
+// Number of cells where one more obstacle makes the guard walk in a loop
+pub fn loop_obstacle_count(lab: &str) -> SolutionResult {
+    let (cells, guard) = parse_lab(lab.lines().map(str::to_string));
+    Ok(loop_obstacles(&cells, &guard)?.len().to_string())
+}
+
+fn loop_obstacles(cells: &Array2<Cell>, guard: &Guard) -> Result<Vec<Vec2<usize>>, AnyError> {
+    let jump_table = JumpTable::new(cells);
+
+    // The guard reaches each new obstacle on its first visit of the cell, so the loop
+    // check can start right in front of it
+    let mut candidates = Vec::new();
+    walk_guard(&mut cells.clone(), &mut guard.clone(), |visitor| {
+        if visitor.position != guard.position {
+            let mut before = visitor.clone();
+            before.move_forward(-1);
+            candidates.push((before, visitor.position.convert().unwrap()));
+        }
+    })
+    .exit_or_err()?;
+
+    let mut visited = BitSet::new(cells.len() * 4);
+    Ok(candidates
         .into_iter()
-        .filter(|idx| {
-            let mut cells_clone = cells.clone();
-            cells_clone[*idx] = Cell::Obstacle;
-            !walk_guard(&mut cells_clone, &mut guard.clone(), |_| {})
+        .filter(|(before, obstacle)| {
+            visited.clear();
+            jump_table.is_loop(before.clone(), *obstacle, &mut visited)
         })
-        .count() as i64;
+        .map(|(_, obstacle)| obstacle)
+        .collect())
+}
 
-    Ok(loops.to_string())
+pub fn visualize(recorder: &mut Recorder) -> Result<(), AnyError> {
+    let file = get_text_file(INPUT_URL)?;
+    let (mut cells, mut guard) = read_input(file);
+    let mut frame = render_cells(&cells);
+    recorder.record(frame.clone());
+    let outcome = walk_guard(&mut cells, &mut guard, |guard| {
+        let index = guard.position.convert().unwrap();
+        frame.set(index, Glyph::new(VISITED, Rgb::YELLOW));
+        recorder.record_step(|| {
+            let mut frame = frame.clone();
+            frame.set(index, guard_glyph(guard));
+            frame
+        });
+    });
+    let mut frame = render_cells(&cells);
+    if let WalkOutcome::Cycle { entry, cells, .. } = outcome {
+        for index in cells {
+            frame.set(index, Glyph::new(VISITED, Rgb::ORANGE));
+        }
+        frame.set(entry.position.convert().unwrap(), guard_glyph(&entry));
+    }
+    recorder.record(frame);
+    Ok(())
 }
 
 fn read_input(file: File) -> (Array2<Cell>, Guard) {
+    parse_lab(BufReader::new(file).lines().map(Result::unwrap))
+}
+
+fn parse_lab(lines: impl Iterator<Item = String>) -> (Array2<Cell>, Guard) {
     let mut cells = Vec::new();
     let mut guard = None;
 
-    let rows = BufReader::new(file)
-        .lines()
+    let rows = lines
         .enumerate()
         .map(|(row, line)| {
-            cells.extend(line.unwrap().chars().enumerate().map(|(col, ch)| match ch {
+            cells.extend(line.chars().enumerate().map(|(col, ch)| match ch {
                 EMPTY => Cell::Empty,
                 OBSTACTLE => Cell::Obstacle,
                 _ => {
@@ -137,58 +340,234 @@ fn read_input(file: File) -> (Array2<Cell>, Guard) {
     )
 }
 
-fn walk_guard<F>(cells: &mut Array2<Cell>, guard: &mut Guard, mut predicate: F) -> bool
+fn walk_guard<F>(cells: &mut Array2<Cell>, guard: &mut Guard, mut predicate: F) -> WalkOutcome
 where
-    F: FnMut(&Guard) -> (),
+    F: FnMut(&Guard),
 {
+    let start = guard.clone();
+    let mut steps = 0;
+    let mut turns_in_place = 0;
+    let mut last_position = guard.position;
     while let Some(cell) = guard
         .position
         .convert::<usize>()
-        .map(|idx| cells.get_mut(idx))
-        .flatten()
+        .and_then(|idx| cells.get_mut(idx))
     {
         match cell {
             Cell::Obstacle => {
                 guard.move_forward(-1);
                 guard.direction = guard.direction.rotate();
+                // Take back the step into the obstacle
+                steps -= 1;
+                // Obstacles on all four sides keep the guard from revisiting a cell
+                turns_in_place += 1;
+                if turns_in_place == 4 {
+                    return trace_cycle(cells, start, guard.clone());
+                }
             }
             Cell::Empty => {
                 *cell = Cell::Visited(guard.direction.clone());
-                predicate(&guard);
+                predicate(guard);
+                turns_in_place = 0;
             }
             Cell::Visited(prev_direction) if *prev_direction == guard.direction => {
-                return false;
+                return trace_cycle(cells, start, guard.clone());
             }
-            _ => {}
+            _ => turns_in_place = 0,
         }
+        last_position = guard.position;
         guard.move_forward(1);
-        // display_cells(&cells, &guard);
-        // println!();
-    }
-    true
-}
-
-// fn display_cells(cells: &Array2<Cell>, guard: &Guard) {
-//     for (y, row) in cells.axis_iter(Axis(0)).enumerate() {
-//         for (x, cell) in row.iter().enumerate() {
-//             print!(
-//                 "{}",
-//                 if guard.position == (Vec2 {x: x as isize, y: y as isize}) {
-//                     match guard.direction {
-//                         Direction::Up => GUARD_UP,
-//                         Direction::Right => GUARD_RIGHT,
-//                         Direction::Down => GUARD_DOWN,
-//                         Direction::Left => GUARD_LEFT,
-//                     }
-//                 } else {
-//                     match cell {
-//                         Cell::Obstacle => OBSTACTLE,
-//                         Cell::Empty => EMPTY,
-//                         Cell::Visited(_) => 'X',
-//                     }
-//                 }
-//             )
-//         }
-//         println!();
-//     }
-// }
+        steps += 1;
+    }
+    WalkOutcome::Exit {
+        position: last_position.convert().unwrap(),
+        steps,
+    }
+}
+
+// Any repeated state lies on the cycle, the entry is the first state of the walk on it
+fn trace_cycle(cells: &Array2<Cell>, start: Guard, on_cycle: Guard) -> WalkOutcome {
+    let state = |guard: &Guard| (guard.position, guard.direction.clone());
+    let mut states = FxHashSet::default();
+    let mut cycle_cells = Vec::new();
+    let mut length = 0;
+    let mut guard = on_cycle;
+    while states.insert(state(&guard)) {
+        cycle_cells.push(guard.position.convert().unwrap());
+        if turn_or_step(cells, &mut guard) {
+            length += 1;
+        }
+    }
+    cycle_cells.sort_unstable_by_key(|cell: &Vec2<usize>| (cell.y, cell.x));
+    cycle_cells.dedup();
+
+    let mut entry = start;
+    while !states.contains(&state(&entry)) {
+        turn_or_step(cells, &mut entry);
+    }
+    WalkOutcome::Cycle {
+        entry,
+        length,
+        cells: cycle_cells,
+    }
+}
+
+// Returns whether the guard moved, it never leaves the map while on a cycle
+fn turn_or_step(cells: &Array2<Cell>, guard: &mut Guard) -> bool {
+    let mut ahead = guard.clone();
+    ahead.move_forward(1);
+    let blocked = ahead
+        .position
+        .convert::<usize>()
+        .and_then(|idx| cells.get(idx))
+        .is_some_and(|cell| *cell == Cell::Obstacle);
+    if blocked {
+        guard.direction = guard.direction.rotate();
+    } else {
+        *guard = ahead;
+    }
+    !blocked
+}
+
+fn render_cells(cells: &Array2<Cell>) -> Frame {
+    Frame::from_grid(cells, |cell| match cell {
+        Cell::Obstacle => Glyph::new(OBSTACTLE, Rgb::GREY),
+        Cell::Empty => Glyph::new(EMPTY, Rgb::DARK_GREY),
+        Cell::Visited(_) => Glyph::new(VISITED, Rgb::YELLOW),
+    })
+}
+
+fn guard_glyph(guard: &Guard) -> Glyph {
+    let symbol = match guard.direction {
+        Direction::Up => GUARD_UP,
+        Direction::Right => GUARD_RIGHT,
+        Direction::Down => GUARD_DOWN,
+        Direction::Left => GUARD_LEFT,
+    };
+    Glyph::new(symbol, Rgb::RED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lab(text: &str) -> (Array2<Cell>, Guard) {
+        parse_lab(text.lines().map(str::to_string))
+    }
+
+    // Places each obstacle and walks the whole lab again
+    fn loop_obstacles_by_rewalk(cells: &Array2<Cell>, guard: &Guard) -> Vec<Vec2<usize>> {
+        cells
+            .indexed_iter()
+            .map(|(index, cell)| (Vec2::from_index_tuple(index), cell))
+            .filter(|(index, cell)| {
+                **cell == Cell::Empty && index.convert() != Some(guard.position)
+            })
+            .filter(|(index, _)| {
+                let mut cells = cells.clone();
+                cells[*index] = Cell::Obstacle;
+                let outcome = walk_guard(&mut cells, &mut guard.clone(), |_| {});
+                matches!(outcome, WalkOutcome::Cycle { .. })
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    // Deterministic lab with about one obstacle in six cells
+    fn generate_lab(seed: u64, cols: usize, rows: usize) -> String {
+        let mut state = seed;
+        let mut text = String::new();
+        for y in 0..rows {
+            for x in 0..cols {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                text.push(if (x, y) == (cols / 2, rows / 2) {
+                    GUARD_UP
+                } else if (state >> 33).is_multiple_of(6) {
+                    OBSTACTLE
+                } else {
+                    EMPTY
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn jump_table_matches_rewalk() {
+        let example = "\
+....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#...";
+        let labs = [example.to_string()]
+            .into_iter()
+            .chain((0..40).map(|seed| generate_lab(seed, 13, 9)));
+        let mut checked = 0;
+        for text in labs {
+            let (cells, guard) = lab(&text);
+            // Labs the guard already loops in have no answer
+            let Ok(mut obstacles) = loop_obstacles(&cells, &guard) else {
+                continue;
+            };
+            obstacles.sort_unstable_by_key(|index| (index.y, index.x));
+            assert_eq!(
+                obstacles,
+                loop_obstacles_by_rewalk(&cells, &guard),
+                "{text}"
+            );
+            checked += 1;
+        }
+        assert!(checked > 20);
+        assert_eq!(loop_obstacle_count(example).unwrap(), "6");
+    }
+
+    #[test]
+    fn walk_exits() {
+        let (mut cells, mut guard) = lab("....#\n.....\n.^...\n.....");
+        let mut visited = 0;
+        let outcome = walk_guard(&mut cells, &mut guard, |_| visited += 1);
+        let (position, steps) = outcome.exit_or_err().unwrap();
+        assert_eq!(position, Vec2 { x: 1, y: 0 });
+        assert_eq!((steps, visited), (3, 3));
+    }
+
+    #[test]
+    fn walk_boxed_in() {
+        let (mut cells, mut guard) = lab("###\n#^#\n###");
+        match walk_guard(&mut cells, &mut guard, |_| {}) {
+            WalkOutcome::Cycle {
+                entry,
+                length,
+                cells,
+            } => {
+                assert_eq!(entry.position, Vec2 { x: 1, y: 1 });
+                assert_eq!(entry.direction, Direction::Up);
+                assert_eq!(length, 0);
+                assert_eq!(cells, [Vec2 { x: 1, y: 1 }]);
+            }
+            outcome => panic!("Expected a cycle, got {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn walk_cycle() {
+        let (mut cells, mut guard) = lab(".#...\n....#\n.^...\n#....\n...#.");
+        let error = walk_guard(&mut cells, &mut guard, |_| {})
+            .exit_or_err()
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Guard walks in a cycle of 8 steps entered at (1, 2) facing Up"
+        );
+    }
+}
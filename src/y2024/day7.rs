@@ -1,51 +1,128 @@
-use crate::{get_text_file, SolutionResult};
-use itertools::{repeat_n, Itertools};
+use crate::{
+    get_text_file,
+    math::{checked_sum, Answer},
+    AnyError, SolutionResult,
+};
+use itertools::Itertools;
+use num::{CheckedAdd, CheckedMul, CheckedSub, Integer, Zero};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::{
+    fmt::Write,
     fs::File,
     io::{BufRead, BufReader},
 };
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/7/input";
 
+struct Operator {
+    symbol: &'static str,
+    apply: fn(&Answer, &Answer) -> Option<Answer>,
+    // Left operand which gives the target with the right operand, if there is one
+    inverse: fn(&Answer, &Answer) -> Option<Answer>,
+}
+
+const ADD: Operator = Operator {
+    symbol: "+",
+    apply: add,
+    inverse: sub,
+};
+const MUL: Operator = Operator {
+    symbol: "*",
+    apply: mul,
+    inverse: div_exact,
+};
+const CONCAT: Operator = Operator {
+    symbol: "||",
+    apply: concat,
+    inverse: strip_suffix,
+};
+
+const OPERATORS_1: [Operator; 2] = [ADD, MUL];
+const OPERATORS_2: [Operator; 3] = [ADD, MUL, CONCAT];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Strategy {
+    Forward,
+    Backward,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct SearchStats {
+    pub explored: usize,
+    pub pruned: usize,
+}
+
+impl SearchStats {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            explored: self.explored + other.explored,
+            pruned: self.pruned + other.pruned,
+        }
+    }
+}
+
+struct Evaluation<'a> {
+    // Operators in equation order
+    equation: Option<Vec<&'a Operator>>,
+    stats: SearchStats,
+}
+
 pub fn part_1() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
+    let equations = iter_input(file).collect_vec();
 
-    let result: i64 = iter_input(file)
-        .filter_map(|(value, operands)| {
-            let operators = [|lhs, rhs| lhs + rhs, |lhs, rhs| lhs * rhs];
-            if check_operators(value, &operands, &operators) {
-                Some(value)
-            } else {
-                None
-            }
-        })
-        .sum();
+    let result = total_calibration(&equations, &OPERATORS_1, Strategy::Backward)?;
 
     Ok(result.to_string())
 }
 
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
+    let equations = iter_input(file).collect_vec();
 
-    let result: i64 = iter_input(file)
-        .filter_map(|(value, operands)| {
-            let operators = [
-                |lhs, rhs| lhs + rhs,
-                |lhs, rhs| lhs * rhs,
-                |lhs, rhs| lhs * 10_i64.pow(num_digits(rhs)) + rhs,
-            ];
-            if check_operators(value, &operands, &operators) {
-                Some(value)
-            } else {
-                None
-            }
-        })
-        .sum();
+    let result = total_calibration(&equations, &OPERATORS_2, Strategy::Backward)?;
 
     Ok(result.to_string())
 }
 
-pub fn iter_input(file: File) -> impl Iterator<Item = (i64, Vec<i64>)> {
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let equations = iter_input(file).collect_vec();
+
+    let mut explanation = String::new();
+    let evaluations = evaluate_all(&equations, operators(part)?, Strategy::Backward);
+    for ((value, operands), Evaluation { equation, stats }) in equations.iter().zip(evaluations) {
+        match equation {
+            Some(equation) => {
+                write!(explanation, "{value} = {}", operands[0])?;
+                for (operator, operand) in equation.iter().zip(&operands[1..]) {
+                    write!(explanation, " {} {operand}", operator.symbol)?;
+                }
+            }
+            None => write!(explanation, "{value} has no solution")?,
+        }
+        writeln!(
+            explanation,
+            " (explored {}, pruned {})",
+            stats.explored, stats.pruned
+        )?;
+    }
+    Ok(explanation)
+}
+
+pub fn search_stats(part: u32, strategy: Strategy) -> Result<SearchStats, AnyError> {
+    let file = get_text_file(INPUT_URL)?;
+    let equations = iter_input(file).collect_vec();
+
+    Ok(evaluate_all(&equations, operators(part)?, strategy)
+        .into_iter()
+        .fold(SearchStats::default(), |total, evaluation| {
+            total.merge(evaluation.stats)
+        }))
+}
+
+pub fn iter_input(file: File) -> impl Iterator<Item = (Answer, Vec<Answer>)> {
     BufReader::new(file).lines().map(|line| {
         let line = line.unwrap();
         let (value, operands) = line.split(':').collect_tuple().unwrap();
@@ -59,32 +136,158 @@ pub fn iter_input(file: File) -> impl Iterator<Item = (i64, Vec<i64>)> {
     })
 }
 
-fn check_operators(value: i64, operands: &[i64], operators: &[fn(i64, i64) -> i64]) -> bool {
-    let mut operands_iter = operands.iter();
-    if let Some(init_operand) = operands_iter.next() {
-        repeat_n(operators.iter(), operands.len() - 1)
-            .multi_cartesian_product()
-            .any(|operators| {
-                value
-                    == operands_iter
-                        .clone()
-                        .zip(operators.iter())
-                        .fold(*init_operand, |lhs, (rhs, op)| op(lhs, *rhs))
-            })
-    } else {
-        value == 0
+fn operators(part: u32) -> Result<&'static [Operator], AnyError> {
+    match part {
+        1 => Ok(&OPERATORS_1),
+        2 => Ok(&OPERATORS_2),
+        _ => Err("Part does not exist".into()),
     }
 }
 
-fn num_digits(n: i64) -> u32 {
-    let mut count = 0;
-    let mut num = n.abs();
-    if num == 0 {
-        return 1;
+fn total_calibration(
+    equations: &[(Answer, Vec<Answer>)],
+    operators: &[Operator],
+    strategy: Strategy,
+) -> Result<Answer, AnyError> {
+    checked_sum(
+        equations
+            .iter()
+            .zip(evaluate_all(equations, operators, strategy))
+            .filter(|(_, evaluation)| evaluation.equation.is_some())
+            .map(|((value, _), _)| value.to_owned()),
+    )
+}
+
+#[cfg(feature = "parallel")]
+fn evaluate_all<'a>(
+    equations: &[(Answer, Vec<Answer>)],
+    operators: &'a [Operator],
+    strategy: Strategy,
+) -> Vec<Evaluation<'a>> {
+    equations
+        .par_iter()
+        .map(|(value, operands)| evaluate(value, operands, operators, strategy))
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn evaluate_all<'a>(
+    equations: &[(Answer, Vec<Answer>)],
+    operators: &'a [Operator],
+    strategy: Strategy,
+) -> Vec<Evaluation<'a>> {
+    equations
+        .iter()
+        .map(|(value, operands)| evaluate(value, operands, operators, strategy))
+        .collect()
+}
+
+fn evaluate<'a>(
+    value: &Answer,
+    operands: &[Answer],
+    operators: &'a [Operator],
+    strategy: Strategy,
+) -> Evaluation<'a> {
+    let mut stats = SearchStats::default();
+    let equation = match (strategy, operands) {
+        (_, []) => value.is_zero().then(Vec::new),
+        (Strategy::Forward, [first, rest @ ..]) => {
+            solve_forward(value, first.to_owned(), rest, operators, &mut stats).map(
+                |mut equation| {
+                    equation.reverse();
+                    equation
+                },
+            )
+        }
+        (Strategy::Backward, _) => solve_backward(value, operands, operators, &mut stats),
+    };
+    Evaluation { equation, stats }
+}
+
+// Folds left to right and prunes once the intermediate result exceeds the value, the
+// operators never decrease it for positive operands. Returns the operators in reverse.
+fn solve_forward<'a>(
+    value: &Answer,
+    lhs: Answer,
+    operands: &[Answer],
+    operators: &'a [Operator],
+    stats: &mut SearchStats,
+) -> Option<Vec<&'a Operator>> {
+    stats.explored += 1;
+    let [rhs, rest @ ..] = operands else {
+        return (lhs == *value).then(Vec::new);
+    };
+    operators.iter().find_map(|operator| {
+        let Some(result) = (operator.apply)(&lhs, rhs).filter(|result| result <= value) else {
+            stats.pruned += 1;
+            return None;
+        };
+        let mut equation = solve_forward(value, result, rest, operators, stats)?;
+        equation.push(operator);
+        Some(equation)
+    })
+}
+
+// Works backwards from the value by undoing the last operator, which prunes every
+// branch where the inverse does not exist. Returns the operators in equation order.
+fn solve_backward<'a>(
+    value: &Answer,
+    operands: &[Answer],
+    operators: &'a [Operator],
+    stats: &mut SearchStats,
+) -> Option<Vec<&'a Operator>> {
+    stats.explored += 1;
+    let [rest @ .., rhs] = operands else {
+        return value.is_zero().then(Vec::new);
+    };
+    if rest.is_empty() {
+        return (rhs == value).then(Vec::new);
     }
-    while num > 0 {
-        count += 1;
-        num /= 10;
+    operators.iter().find_map(|operator| {
+        let Some(lhs) = (operator.inverse)(value, rhs) else {
+            stats.pruned += 1;
+            return None;
+        };
+        let mut equation = solve_backward(&lhs, rest, operators, stats)?;
+        debug_assert_eq!((operator.apply)(&lhs, rhs).as_ref(), Some(value));
+        equation.push(operator);
+        Some(equation)
+    })
+}
+
+fn add(lhs: &Answer, rhs: &Answer) -> Option<Answer> {
+    CheckedAdd::checked_add(lhs, rhs)
+}
+
+fn sub(value: &Answer, rhs: &Answer) -> Option<Answer> {
+    CheckedSub::checked_sub(value, rhs).filter(|lhs| *lhs >= Answer::zero())
+}
+
+fn mul(lhs: &Answer, rhs: &Answer) -> Option<Answer> {
+    CheckedMul::checked_mul(lhs, rhs)
+}
+
+// A zero operand would allow any left operand, the puzzle operands are positive
+fn div_exact(value: &Answer, rhs: &Answer) -> Option<Answer> {
+    let (lhs, remainder) = (!rhs.is_zero()).then(|| value.div_rem(rhs))?;
+    remainder.is_zero().then_some(lhs)
+}
+
+fn concat(lhs: &Answer, rhs: &Answer) -> Option<Answer> {
+    add(&mul(lhs, &concat_shift(rhs)?)?, rhs)
+}
+
+fn strip_suffix(value: &Answer, rhs: &Answer) -> Option<Answer> {
+    div_exact(&sub(value, rhs)?, &concat_shift(rhs)?)
+}
+
+fn concat_shift(rhs: &Answer) -> Option<Answer> {
+    let ten = Answer::from(10_u8);
+    let mut shift = Answer::from(10_u8);
+    let mut rest = Integer::div_floor(rhs, &ten);
+    while !rest.is_zero() {
+        shift = mul(&shift, &ten)?;
+        rest = Integer::div_floor(&rest, &ten);
     }
-    count
+    Some(shift)
 }
@@ -1,9 +1,9 @@
 use itertools::Itertools;
+use num::Integer;
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
-    collections::HashSet,
+    fmt::Write,
     fs::File,
-    hash::BuildHasher,
     io::{BufRead, BufReader},
 };
 
@@ -11,51 +11,210 @@ use crate::{get_text_file, math::Vec2, SolutionResult};
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/8/input";
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let (antenna_types, bounds) = read_input(file);
-    let mut antinodes = FxHashSet::<Vec2<isize>>::default();
-    for antennas in antenna_types {
-        for (antenna_1, antenna_2) in antennas
-            .into_iter()
-            .combinations(2)
-            .map(|x| x.into_iter().collect_tuple::<(_, _)>().unwrap())
-        {
-            insert_antinode_pair(antenna_1, antenna_2, bounds, &mut antinodes);
+const EMPTY: char = '.';
+const ANTINODE: char = '#';
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ResonanceRule {
+    // Points in line beyond the antennas where one antenna is k times as far away as
+    // the other
+    DistanceMultiple(isize),
+    // Points in line between the antennas where one antenna is k times as far away as
+    // the other
+    InnerDistanceMultiple(isize),
+    // Every antenna-to-antenna step along the line
+    Harmonics,
+    // Every grid point on the line
+    Lattice,
+}
+
+struct AntennaMap {
+    antennas: FxHashMap<char, Vec<Vec2<isize>>>,
+    bounds: (Vec2<isize>, Vec2<isize>),
+}
+
+impl AntennaMap {
+    fn antinodes(&self, rule: ResonanceRule) -> FxHashMap<char, FxHashSet<Vec2<isize>>> {
+        self.antennas
+            .iter()
+            .map(|(frequency, antennas)| {
+                let mut antinodes = FxHashSet::default();
+                for (antenna_1, antenna_2) in antennas.iter().tuple_combinations() {
+                    self.insert_antinodes(*antenna_1, *antenna_2, rule, &mut antinodes);
+                }
+                (*frequency, antinodes)
+            })
+            .collect()
+    }
+
+    fn insert_antinodes(
+        &self,
+        antenna_1: Vec2<isize>,
+        antenna_2: Vec2<isize>,
+        rule: ResonanceRule,
+        antinodes: &mut FxHashSet<Vec2<isize>>,
+    ) {
+        let diff = antenna_2 - antenna_1;
+        match rule {
+            // antenna_1 + diff * t with |t| = k * |t - 1|, so t = k / (k - 1) outside or
+            // t = k / (k + 1) between the antennas, and the mirrored 1 - t
+            ResonanceRule::DistanceMultiple(k) => {
+                self.insert_multiples(antenna_1, antenna_2, k, k - 1, antinodes)
+            }
+            ResonanceRule::InnerDistanceMultiple(k) => {
+                self.insert_multiples(antenna_1, antenna_2, k, k + 1, antinodes)
+            }
+            ResonanceRule::Harmonics => self.insert_line(antenna_1, diff, antinodes),
+            ResonanceRule::Lattice => {
+                let gcd = diff.x.gcd(&diff.y);
+                let step = Vec2 {
+                    x: diff.x / gcd,
+                    y: diff.y / gcd,
+                };
+                self.insert_line(antenna_1, step, antinodes);
+            }
         }
     }
-    Ok(antinodes.len().to_string())
+
+    fn insert_multiples(
+        &self,
+        antenna_1: Vec2<isize>,
+        antenna_2: Vec2<isize>,
+        k: isize,
+        denominator: isize,
+        antinodes: &mut FxHashSet<Vec2<isize>>,
+    ) {
+        let diff = antenna_2 - antenna_1;
+        if denominator == 0 || diff.x % denominator != 0 || diff.y % denominator != 0 {
+            return;
+        }
+        let step = Vec2 {
+            x: diff.x / denominator,
+            y: diff.y / denominator,
+        };
+        for antinode in [antenna_1 + step * k, antenna_2 - step * k] {
+            if antinode.in_bounds(self.bounds) {
+                antinodes.insert(antinode);
+            }
+        }
+    }
+
+    fn insert_line(
+        &self,
+        origin: Vec2<isize>,
+        step: Vec2<isize>,
+        antinodes: &mut FxHashSet<Vec2<isize>>,
+    ) {
+        for step in [step, -step] {
+            let mut antinode = origin;
+            while antinode.in_bounds(self.bounds) {
+                antinodes.insert(antinode);
+                antinode = antinode + step;
+            }
+        }
+    }
+
+    fn render(&self, frequency: char, antinodes: &FxHashSet<Vec2<isize>>) -> String {
+        let (_, Vec2 { x: cols, y: rows }) = self.bounds;
+        let antennas = &self.antennas[&frequency];
+        let mut text = String::new();
+        for y in 0..rows {
+            text.extend((0..cols).map(|x| {
+                let position = Vec2 { x, y };
+                if antennas.contains(&position) {
+                    frequency
+                } else if antinodes.contains(&position) {
+                    ANTINODE
+                } else {
+                    EMPTY
+                }
+            }));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+pub fn part_1() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let antenna_map = read_input(file);
+
+    let antinodes = count_unique(antenna_map.antinodes(ResonanceRule::DistanceMultiple(2)));
+    Ok(antinodes.to_string())
 }
 
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
-    let (antenna_types, bounds) = read_input(file);
-    let mut antinodes = FxHashSet::<Vec2<isize>>::default();
-    for antennas in antenna_types {
-        for (antenna_1, antenna_2) in antennas
-            .into_iter()
-            .combinations(2)
-            .map(|x| x.into_iter().collect_tuple::<(_, _)>().unwrap())
-        {
-            insert_antinodes(antenna_1, antenna_2, bounds, &mut antinodes);
-        }
+    let antenna_map = read_input(file);
+
+    let antinodes = count_unique(antenna_map.antinodes(ResonanceRule::Lattice));
+    Ok(antinodes.to_string())
+}
+
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let antenna_map = read_input(file);
+
+    let rule = match part {
+        1 => ResonanceRule::DistanceMultiple(2),
+        2 => ResonanceRule::Lattice,
+        _ => return Err("Part does not exist".into()),
+    };
+    let mut explanation = String::new();
+    if let ResonanceRule::DistanceMultiple(k) = rule {
+        writeln!(
+            explanation,
+            "{} more antinodes between the antennas\n",
+            count_unique(antenna_map.antinodes(ResonanceRule::InnerDistanceMultiple(k)))
+        )?;
+    }
+    if rule == ResonanceRule::Lattice {
+        // Differs when two antennas are not the closest grid points on their line
+        writeln!(
+            explanation,
+            "{} antinodes with antenna-to-antenna steps only\n",
+            count_unique(antenna_map.antinodes(ResonanceRule::Harmonics))
+        )?;
     }
-    Ok(antinodes.len().to_string())
+    for (frequency, antinodes) in antenna_map
+        .antinodes(rule)
+        .into_iter()
+        .sorted_by_key(|(frequency, _)| *frequency)
+    {
+        writeln!(
+            explanation,
+            "{frequency}: {} antinodes\n{}",
+            antinodes.len(),
+            antenna_map.render(frequency, &antinodes)
+        )?;
+    }
+    Ok(explanation)
+}
+
+fn count_unique(antinodes: FxHashMap<char, FxHashSet<Vec2<isize>>>) -> usize {
+    antinodes
+        .into_values()
+        .flatten()
+        .collect::<FxHashSet<_>>()
+        .len()
 }
 
-fn read_input(file: File) -> (Vec<Vec<Vec2<isize>>>, (Vec2<isize>, Vec2<isize>)) {
-    let mut map = FxHashMap::<char, Vec<Vec2<isize>>>::default();
+fn read_input(file: File) -> AntennaMap {
+    parse_antenna_map(BufReader::new(file).lines().map(Result::unwrap))
+}
+
+fn parse_antenna_map(lines: impl Iterator<Item = String>) -> AntennaMap {
+    let mut antennas = FxHashMap::<char, Vec<Vec2<isize>>>::default();
 
-    let mut lines_iter = BufReader::new(file).lines().peekable();
+    let mut lines_iter = lines.peekable();
 
-    let cols = lines_iter.peek().unwrap().as_ref().unwrap().chars().count() as isize;
+    let cols = lines_iter.peek().map_or(0, |line| line.chars().count()) as isize;
 
     let rows = lines_iter
         .enumerate()
         .map(|(row, line)| {
-            let line = line.unwrap();
-            for (col, ch) in line.char_indices().filter(|(_, ch)| *ch != '.') {
-                map.entry(ch).or_insert(Vec::new()).push(Vec2 {
+            for (col, ch) in line.char_indices().filter(|(_, ch)| *ch != EMPTY) {
+                antennas.entry(ch).or_default().push(Vec2 {
                     x: col as isize,
                     y: row as isize,
                 });
@@ -63,44 +222,78 @@ fn read_input(file: File) -> (Vec<Vec<Vec2<isize>>>, (Vec2<isize>, Vec2<isize>))
         })
         .count() as isize;
 
-    (
-        map.into_values().collect(),
-        (Vec2 { x: 0, y: 0 }, Vec2 { x: cols, y: rows }),
-    )
+    AntennaMap {
+        antennas,
+        bounds: (Vec2 { x: 0, y: 0 }, Vec2 { x: cols, y: rows }),
+    }
 }
 
-fn insert_antinode_pair<S: BuildHasher>(
-    antenna_1: Vec2<isize>,
-    antenna_2: Vec2<isize>,
-    bounds: (Vec2<isize>, Vec2<isize>),
-    antinodes: &mut HashSet<Vec2<isize>, S>,
-) {
-    let diff = antenna_2 - antenna_1;
-    let antinode_1 = antenna_1 - diff;
-    let antinode_2 = antenna_2 + diff;
-    if antinode_1.in_bounds(bounds) {
-        antinodes.insert(antinode_1);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn antinodes(rows: &[&str], rule: ResonanceRule) -> Vec<Vec2<isize>> {
+        let antenna_map = parse_antenna_map(rows.iter().map(|row| row.to_string()));
+        let mut antinodes = antenna_map
+            .antinodes(rule)
+            .into_values()
+            .flatten()
+            .collect_vec();
+        antinodes.sort_unstable_by_key(|antinode| (antinode.y, antinode.x));
+        antinodes
     }
-    if antinode_2.in_bounds(bounds) {
-        antinodes.insert(antinode_2);
+
+    fn v(x: isize, y: isize) -> Vec2<isize> {
+        Vec2 { x, y }
     }
-}
 
-fn insert_antinodes<S: BuildHasher>(
-    antenna_1: Vec2<isize>,
-    antenna_2: Vec2<isize>,
-    bounds: (Vec2<isize>, Vec2<isize>),
-    antinodes: &mut HashSet<Vec2<isize>, S>,
-) {
-    let diff = antenna_2 - antenna_1;
-    let mut antinode_1 = antenna_1;
-    while antinode_1.in_bounds(bounds) {
-        antinodes.insert(antinode_1);
-        antinode_1 = antinode_1 - diff;
+    #[test]
+    fn distance_multiple() {
+        assert_eq!(
+            antinodes(&["a..a......"], ResonanceRule::DistanceMultiple(2)),
+            vec![v(6, 0)]
+        );
+        assert_eq!(
+            antinodes(
+                &[
+                    "..........",
+                    "...a......",
+                    "......a...",
+                    "..........",
+                    ".........."
+                ],
+                ResonanceRule::DistanceMultiple(2)
+            ),
+            vec![v(0, 0), v(9, 3)]
+        );
+    }
+
+    #[test]
+    fn inner_distance_multiple() {
+        assert_eq!(
+            antinodes(&["a..a......"], ResonanceRule::InnerDistanceMultiple(2)),
+            vec![v(1, 0), v(2, 0)]
+        );
+        assert!(antinodes(&["a.a......."], ResonanceRule::InnerDistanceMultiple(2)).is_empty());
     }
-    let mut antinode_2 = antenna_2;
-    while antinode_2.in_bounds(bounds) {
-        antinodes.insert(antinode_2);
-        antinode_2 = antinode_2 + diff;
+
+    #[test]
+    fn harmonics() {
+        assert_eq!(
+            antinodes(&["..a.a....."], ResonanceRule::Harmonics),
+            vec![v(0, 0), v(2, 0), v(4, 0), v(6, 0), v(8, 0)]
+        );
+    }
+
+    #[test]
+    fn lattice() {
+        assert_eq!(
+            antinodes(&["a..", "...", "..a", "...", "..."], ResonanceRule::Lattice),
+            vec![v(0, 0), v(1, 1), v(2, 2)]
+        );
+        assert_eq!(
+            antinodes(&["a.a.", "...."], ResonanceRule::Lattice),
+            vec![v(0, 0), v(1, 0), v(2, 0), v(3, 0)]
+        );
     }
 }
@@ -1,100 +1,296 @@
-use crate::{get_text_file, SolutionResult};
-use std::{error::Error, fs::File, io::Read};
+use crate::{
+    get_text_file,
+    math::{checked_product, to_answer, try_checked_sum, Answer, OrOverflow},
+    AnyError, SolutionResult,
+};
+use num::{CheckedAdd, CheckedMul, Zero};
+use std::{cmp::Reverse, collections::BinaryHeap, error::Error, fmt::Write, fs::File, io::Read};
 
 const INPUT_URL: &str = "https://adventofcode.com/2024/day/9/input";
 
+const MAX_BLOCK_SIZE: usize = 9;
+
 #[derive(Clone, Debug)]
 struct Block {
     offset: usize,
     size: usize,
 }
 
-pub fn part_1() -> SolutionResult {
-    let file = get_text_file(INPUT_URL)?;
-    let (used_blocks, free_blocks) = read_input(file)?;
+#[derive(Clone, Debug)]
+struct DiskMap {
+    // Indexed by file id
+    files: Vec<Block>,
+    free: Vec<Block>,
+}
 
-    let mut used_iter = used_blocks.into_iter().enumerate().rev().peekable();
-    let mut free_iter = free_blocks.into_iter().peekable();
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum CompactStrategy {
+    Blocks,
+    WholeFiles,
+    DefragmentToEnd,
+}
 
-    let mut checksum = 0;
+// Part of a file placed on the compacted disk
+#[derive(Clone, Debug)]
+struct Segment {
+    id: usize,
+    block: Block,
+}
 
-    while match (used_iter.peek(), free_iter.peek()) {
-        (Some((_, used)), Some(free)) => used.offset > free.offset,
-        _ => false,
-    } {
-        let (id, used) = used_iter.peek_mut().unwrap();
-        let free = free_iter.peek_mut().unwrap();
+// Gap offsets indexed by gap size
+struct FreeSpace {
+    gaps: [BinaryHeap<Reverse<usize>>; MAX_BLOCK_SIZE + 1],
+}
 
-        let used_size_prev = used.size;
-        used.size = used.size.saturating_sub(free.size);
+struct Layout {
+    blocks: Vec<Option<usize>>,
+}
+
+impl DiskMap {
+    fn len(&self) -> usize {
+        self.files
+            .iter()
+            .chain(&self.free)
+            .map(|block| block.offset + block.size)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn files_as_segments(&self) -> Vec<Segment> {
+        self.files
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(id, block)| Segment { id, block })
+            .collect()
+    }
+
+    fn compact(&self, strategy: CompactStrategy) -> Vec<Segment> {
+        match strategy {
+            CompactStrategy::Blocks => self.compact_blocks(),
+            CompactStrategy::WholeFiles => self.compact_whole_files(),
+            CompactStrategy::DefragmentToEnd => self.defragment_to_end(),
+        }
+    }
 
-        let blocks_moved = used_size_prev - used.size;
-        checksum +=
-            arithmetic_series(blocks_moved, free.offset, free.offset + blocks_moved - 1) * *id;
+    fn compact_blocks(&self) -> Vec<Segment> {
+        let mut used_iter = self.files.iter().cloned().enumerate().rev().peekable();
+        let mut free_iter = self.free.iter().cloned().peekable();
 
-        free.size -= blocks_moved;
-        free.offset += blocks_moved;
+        let mut segments = Vec::new();
+
+        while match (used_iter.peek(), free_iter.peek()) {
+            (Some((_, used)), Some(free)) => used.offset > free.offset,
+            _ => false,
+        } {
+            let (id, used) = used_iter.peek_mut().unwrap();
+            let free = free_iter.peek_mut().unwrap();
+
+            let used_size_prev = used.size;
+            used.size = used.size.saturating_sub(free.size);
+
+            let blocks_moved = used_size_prev - used.size;
+            segments.push(Segment {
+                id: *id,
+                block: Block {
+                    offset: free.offset,
+                    size: blocks_moved,
+                },
+            });
+
+            free.size -= blocks_moved;
+            free.offset += blocks_moved;
+
+            if used.size == 0 {
+                used_iter.next();
+            }
+            if free.size == 0 {
+                free_iter.next();
+            }
+        }
+        segments.extend(used_iter.map(|(id, block)| Segment { id, block }));
+        segments
+    }
+
+    fn compact_whole_files(&self) -> Vec<Segment> {
+        let mut free_space = FreeSpace::new(&self.free);
+        self.files
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(id, used)| Segment {
+                id,
+                block: Block {
+                    offset: free_space
+                        .take(used.size, used.offset)
+                        .unwrap_or(used.offset),
+                    size: used.size,
+                },
+            })
+            .collect()
+    }
+
+    // Mirror of the whole file compaction, moves files from the front into the rightmost
+    // gap which fits them
+    fn defragment_to_end(&self) -> Vec<Segment> {
+        let mut free_blocks = self.free.clone();
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(id, used)| {
+                let offset = free_blocks
+                    .iter_mut()
+                    .rev()
+                    .take_while(|free| free.offset > used.offset)
+                    .find(|free| used.size <= free.size)
+                    .map(|free| {
+                        free.size -= used.size;
+                        free.offset + free.size
+                    })
+                    .unwrap_or(used.offset);
+                Segment {
+                    id,
+                    block: Block {
+                        offset,
+                        size: used.size,
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+impl FreeSpace {
+    fn new(free: &[Block]) -> Self {
+        let mut gaps: [BinaryHeap<Reverse<usize>>; MAX_BLOCK_SIZE + 1] = Default::default();
+        for block in free.iter().filter(|block| block.size > 0) {
+            gaps[block.size].push(Reverse(block.offset));
+        }
+        Self { gaps }
+    }
 
-        if used.size == 0 {
-            used_iter.next();
+    // Takes the leftmost gap which fits the size and starts before the limit
+    fn take(&mut self, size: usize, before: usize) -> Option<usize> {
+        let (gap_size, offset) = (size..=MAX_BLOCK_SIZE)
+            .filter_map(|gap_size| Some((gap_size, self.gaps[gap_size].peek()?.0)))
+            .min_by_key(|(_, offset)| *offset)
+            .filter(|(_, offset)| *offset < before)?;
+        self.gaps[gap_size].pop();
+        if gap_size > size {
+            self.gaps[gap_size - size].push(Reverse(offset + size));
         }
-        if free.size == 0 {
-            free_iter.next();
+        Some(offset)
+    }
+}
+
+impl Layout {
+    fn new(len: usize, segments: &[Segment]) -> Result<Self, AnyError> {
+        let mut blocks = vec![None; len];
+        for segment in segments {
+            let range = segment.block.offset..segment.block.offset + segment.block.size;
+            let blocks = blocks.get_mut(range).ok_or("Segment outside of the disk")?;
+            if blocks.iter().any(Option::is_some) {
+                return Err(format!("File {} overlaps another file", segment.id).into());
+            }
+            blocks.fill(Some(segment.id));
         }
+        Ok(Self { blocks })
     }
-    for (id, used) in used_iter {
-        checksum += arithmetic_series(used.size, used.offset, used.offset + used.size - 1) * id;
+
+    fn render(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| match block {
+                Some(id) => u32::try_from(*id)
+                    .ok()
+                    .and_then(|id| char::from_digit(id, 36))
+                    .unwrap_or('#'),
+                None => '.',
+            })
+            .collect()
+    }
+
+    fn checksum(&self) -> Result<Answer, AnyError> {
+        try_checked_sum(
+            self.blocks
+                .iter()
+                .enumerate()
+                .filter_map(|(position, id)| Some((position, (*id)?)))
+                .map(|(position, id)| checked_product([to_answer(position)?, to_answer(id)?])),
+        )
     }
+}
+
+pub fn part_1() -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let disk_map = read_input(file)?;
+
+    let checksum = segments_checksum(&disk_map.compact(CompactStrategy::Blocks))?;
 
     Ok(checksum.to_string())
 }
 
 pub fn part_2() -> SolutionResult {
     let file = get_text_file(INPUT_URL)?;
-    let (used_blocks, mut free_blocks) = read_input(file)?;
-
-    let checksum: usize = used_blocks
-        .into_iter()
-        .enumerate()
-        .rev()
-        .map(|(used_id, used)| {
-            free_blocks
-                .iter_mut()
-                .try_for_each(|free| {
-                    if free.offset > used.offset {
-                        Err(None)
-                    } else if used.size <= free.size {
-                        Err(Some(free))
-                    } else {
-                        Ok(())
-                    }
-                })
-                .err()
-                .flatten()
-                .map(|free| {
-                    let checksum =
-                        arithmetic_series(used.size, free.offset, free.offset + used.size - 1)
-                            * used_id;
-                    free.size -= used.size;
-                    free.offset += used.size;
-                    checksum
-                })
-                .unwrap_or(
-                    arithmetic_series(used.size, used.offset, used.offset + used.size - 1)
-                        * used_id,
-                )
-        })
-        .sum();
+    let disk_map = read_input(file)?;
+
+    let checksum = segments_checksum(&disk_map.compact(CompactStrategy::WholeFiles))?;
 
     Ok(checksum.to_string())
 }
 
-fn read_input(mut file: File) -> Result<(Vec<Block>, Vec<Block>), Box<dyn Error>> {
+pub fn whole_files_checksum(disk_map: &str) -> SolutionResult {
+    let checksum =
+        segments_checksum(&parse_disk_map(disk_map).compact(CompactStrategy::WholeFiles))?;
+    Ok(checksum.to_string())
+}
+
+pub fn explain(part: u32) -> SolutionResult {
+    let file = get_text_file(INPUT_URL)?;
+    let disk_map = read_input(file)?;
+
+    let strategies: &[CompactStrategy] = match part {
+        1 => &[CompactStrategy::Blocks],
+        2 => &[
+            CompactStrategy::WholeFiles,
+            CompactStrategy::DefragmentToEnd,
+        ],
+        _ => return Err("Part does not exist".into()),
+    };
+    let mut explanation = String::new();
+    writeln!(
+        explanation,
+        "{}",
+        Layout::new(disk_map.len(), &disk_map.files_as_segments())?.render()
+    )?;
+    for strategy in strategies {
+        let segments = disk_map.compact(*strategy);
+        let layout = Layout::new(disk_map.len(), &segments)?;
+        let checksum = segments_checksum(&segments)?;
+        if layout.checksum()? != checksum {
+            return Err(
+                format!("{strategy:?} checksum {checksum} does not match the block map").into(),
+            );
+        }
+        writeln!(
+            explanation,
+            "{}\n{strategy:?} checksum {checksum}",
+            layout.render()
+        )?;
+    }
+    Ok(explanation)
+}
+
+fn read_input(mut file: File) -> Result<DiskMap, Box<dyn Error>> {
     let mut text = String::new();
     file.read_to_string(&mut text)?;
+    Ok(parse_disk_map(&text))
+}
+
+fn parse_disk_map(text: &str) -> DiskMap {
     let mut left_side: bool = false;
     let mut offset: usize = 0;
-    Ok(text
+    let (files, free) = text
         .chars()
         .filter_map(|ch| {
             let offset_clone = offset;
@@ -112,9 +308,32 @@ fn read_input(mut file: File) -> Result<(Vec<Block>, Vec<Block>), Box<dyn Error>
         .partition(|_| {
             left_side = !left_side;
             left_side
-        }))
+        });
+    DiskMap { files, free }
+}
+
+fn segments_checksum(segments: &[Segment]) -> Result<Answer, AnyError> {
+    try_checked_sum(
+        segments
+            .iter()
+            .map(|segment| file_checksum(segment.id, segment.block.offset, segment.block.size)),
+    )
+}
+
+// Sum of id * position over the blocks of a file
+fn file_checksum(id: usize, offset: usize, size: usize) -> Result<Answer, AnyError> {
+    if size == 0 {
+        return Ok(Answer::zero());
+    }
+    let first = to_answer(offset)?;
+    let last = to_answer(offset + size)?;
+    let positions = arithmetic_series(&to_answer(size)?, &first, &last)?;
+    CheckedMul::checked_mul(&positions, &to_answer(id)?).or_overflow()
 }
 
-fn arithmetic_series(size: usize, first: usize, last: usize) -> usize {
-    size * (first + last) / 2
+// Sum of the integers in first..last
+fn arithmetic_series(size: &Answer, first: &Answer, last: &Answer) -> Result<Answer, AnyError> {
+    let ends = CheckedAdd::checked_add(first, last).or_overflow()?;
+    let ends = ends - Answer::from(1_u8);
+    Ok(CheckedMul::checked_mul(size, &ends).or_overflow()? / Answer::from(2_u8))
 }